@@ -0,0 +1,27 @@
+//! Runtime APIs for querying the token and TCR modules from outside the runtime.
+
+use client::decl_runtime_apis;
+use parity_codec::Codec;
+use rstd::prelude::*;
+pub use crate::token::{AuditReport,TokenMetadata};
+
+decl_runtime_apis! {
+	/// Read-only access to the assets of the token module.
+	pub trait TokenApi<AssetId, Balance> where AssetId: Codec, Balance: Codec {
+		/// Name, symbol and decimals an asset's `Balance` values should be rendered with.
+		fn metadata(asset_id: AssetId) -> TokenMetadata;
+		fn total_supply(asset_id: AssetId) -> Balance;
+		/// Total supply next to the free, reserved and locked balances it should add up to.
+		fn audit(asset_id: AssetId) -> AuditReport<Balance>;
+	}
+
+	/// Read-only access to the TCR.
+	pub trait TcrApi<Hash, AccountId> where Hash: Codec, AccountId: Codec {
+		/// Hashes of up to `count` ranked listings, highest net stake first.
+		fn top_listings(count: u32) -> Vec<Hash>;
+		/// Trust tier of a whitelisted listing, or `None` if it is not whitelisted.
+		fn listing_tier(listing_hash: Hash) -> Option<u8>;
+		/// Evidence hashes or URIs submitted on a challenge's poll, oldest first.
+		fn poll_evidence(challenge_id: u32) -> Vec<(AccountId, Vec<u8>)>;
+	}
+}
@@ -0,0 +1,215 @@
+use crate::tcr::Arbitrator;
+use parity_codec_derive::{Decode,Encode};
+use rstd::prelude::*;
+use runtime_primitives::traits::EnsureOrigin;
+use support::{
+    decl_event,decl_module,decl_storage,dispatch::Result,
+    ensure,StorageMap,StorageValue};
+use {system::ensure_signed,timestamp};
+
+/// Decides TCR challenges by majority of a fixed jury instead of a token-weighted poll.
+pub trait Trait: timestamp::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// Origin that appoints the jury.
+    type JuryOrigin: EnsureOrigin<Self::Origin>;
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode,Decode,Default,Clone,PartialEq)]
+pub struct Dispute<W> {
+    /// Jurors can give their verdicts until this moment.
+    ends: W,
+    /// Verdicts to keep and to remove the listing.
+    keep: u32,
+    remove: u32,
+    appealed: bool,
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Jury {
+        Jurors get(jurors) config(): Vec<T::AccountId>;
+        Disputes get(dispute): map u32 => Option<Dispute<T::Moment>>;
+        Verdicts get(verdict): map (u32, T::AccountId) => Option<bool>;
+        /// When the last dispute to close stops taking verdicts.
+        LastDisputeEnds get(last_dispute_ends): Option<T::Moment>;
+    }
+}
+
+decl_event!(
+    pub enum Event<T> where AccountId = <T as system::Trait>::AccountId {
+        /// Number of jurors now sitting.
+        JurySet(u32),
+        /// Juror, dispute and whether the listing should stay.
+        VerdictGiven(AccountId,u32,bool),
+        /// Dispute and the party that appealed it.
+        Appealed(u32,AccountId),
+    }
+);
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
+        /// Replace the jury. Refused while any dispute still takes verdicts, so that every
+        /// dispute is decided by the jurors sitting when it opened.
+        fn set_jurors(origin, jurors: Vec<T::AccountId>) -> Result {
+            T::JuryOrigin::ensure_origin(origin)?;
+            ensure!(!jurors.is_empty(), "The jury cannot be empty.");
+            let now = <timestamp::Module<T>>::get();
+            ensure!(Self::last_dispute_ends().map_or(true, |ends| ends < now),
+                "The jury cannot be replaced while a dispute is open.");
+
+            Self::deposit_event(RawEvent::JurySet(jurors.len() as u32));
+            <Jurors<T>>::put(jurors);
+            Ok(())
+        }
+
+        /// Rule on whether the challenged listing should stay in the registry.
+        fn give_verdict(origin, dispute_id: u32, keep: bool) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(Self::jurors().contains(&sender), "Only jurors can give verdicts.");
+            let mut dispute = Self::dispute(dispute_id).ok_or("Dispute not found.")?;
+            ensure!(dispute.ends >= <timestamp::Module<T>>::get(), "Jury has already ruled.");
+            ensure!(Self::verdict((dispute_id, sender.clone())).is_none(),
+                "You have already given a verdict.");
+
+            if keep {
+                dispute.keep += 1;
+            } else {
+                dispute.remove += 1;
+            }
+            <Disputes<T>>::insert(dispute_id, dispute);
+            <Verdicts<T>>::insert((dispute_id, sender.clone()), keep);
+
+            Self::deposit_event(RawEvent::VerdictGiven(sender, dispute_id, keep));
+            Ok(())
+        }
+    }
+}
+
+impl<T: Trait> Arbitrator<T::AccountId, T::Moment> for Module<T> {
+    fn create_dispute(dispute_id: u32, ends: T::Moment) {
+        if Self::last_dispute_ends().map_or(true, |last| ends > last) {
+            <LastDisputeEnds<T>>::put(ends.clone());
+        }
+        <Disputes<T>>::insert(dispute_id, Dispute { ends, keep: 0, remove: 0, appealed: false });
+    }
+
+    /// The listing stays only with a strict majority of the verdicts given.
+    fn ruling(dispute_id: u32) -> rstd::result::Result<bool, &'static str> {
+        let dispute = Self::dispute(dispute_id).ok_or("Dispute not found.")?;
+        ensure!(dispute.ends < <timestamp::Module<T>>::get(), "Jury is still deliberating.");
+        Ok(dispute.keep > dispute.remove)
+    }
+
+    /// A ruling can be appealed once. The jury decides the appeal as the new round's dispute.
+    fn appeal(dispute_id: u32, who: &T::AccountId) -> Result {
+        let mut dispute = Self::dispute(dispute_id).ok_or("Dispute not found.")?;
+        ensure!(dispute.ends < <timestamp::Module<T>>::get(), "Jury is still deliberating.");
+        ensure!(!dispute.appealed, "Dispute has already been appealed.");
+
+        dispute.appealed = true;
+        <Disputes<T>>::insert(dispute_id, dispute);
+
+        Self::deposit_event(RawEvent::Appealed(dispute_id, who.clone()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use primitives::{Blake2Hasher,H256};
+    use runtime_io::with_externalities;
+    use runtime_primitives::{
+        testing::{Digest,DigestItem,Header,UintAuthorityId},
+        traits::{BlakeTwo256,IdentityLookup},
+        BuildStorage,
+    };
+    use support::{assert_noop,assert_ok,impl_outer_origin};
+
+    impl_outer_origin!{
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<u64>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+    impl consensus::Trait for Test {
+        type Log = DigestItem;
+        type SessionKey = UintAuthorityId;
+        type InherentOfflineReport = ();
+    }
+    impl timestamp::Trait for Test {
+        type Moment = u64;
+        type OnTimestampSet = ();
+    }
+    impl Trait for Test {
+        type Event = ();
+        type JuryOrigin = system::EnsureRoot<u64>;
+    }
+    type Jury = Module<Test>;
+
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let mut t = system::GenesisConfig::<Test>::default()
+            .build_storage()
+            .unwrap()
+            .0;
+        t.extend(
+            GenesisConfig::<Test> {
+                jurors: vec![],
+            }
+            .build_storage()
+            .unwrap()
+            .0,
+        );
+        t.into()
+    }
+
+    fn set_timestamp(now: u64) {
+        timestamp::Module::<Test>::set_timestamp(now);
+    }
+
+    #[test]
+    fn should_rule_by_jury_majority_and_allow_one_appeal() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(Jury::set_jurors(Origin::signed(1), vec![5, 6, 7]), "bad origin: expected to be a root origin");
+            assert_ok!(Jury::set_jurors(Origin::ROOT, vec![5, 6, 7]));
+            <Jury as Arbitrator<u64, u64>>::create_dispute(1, 10);
+
+            assert_noop!(Jury::give_verdict(Origin::signed(8), 1, true), "Only jurors can give verdicts.");
+            assert_noop!(Jury::set_jurors(Origin::ROOT, vec![8]),
+                "The jury cannot be replaced while a dispute is open.");
+            assert_ok!(Jury::give_verdict(Origin::signed(5), 1, false));
+            assert_ok!(Jury::give_verdict(Origin::signed(6), 1, true));
+            assert_noop!(Jury::give_verdict(Origin::signed(6), 1, false), "You have already given a verdict.");
+            assert_eq!(<Jury as Arbitrator<u64, u64>>::ruling(1), Err("Jury is still deliberating."));
+
+            set_timestamp(11);
+            assert_noop!(Jury::give_verdict(Origin::signed(7), 1, true), "Jury has already ruled.");
+            // A tie removes the listing, as a tied poll does.
+            assert_eq!(<Jury as Arbitrator<u64, u64>>::ruling(1), Ok(false));
+
+            assert_ok!(<Jury as Arbitrator<u64, u64>>::appeal(1, &1));
+            assert_eq!(<Jury as Arbitrator<u64, u64>>::appeal(1, &1), Err("Dispute has already been appealed."));
+            // The appeal is decided in a new round, so this dispute's ruling stands.
+            assert_eq!(<Jury as Arbitrator<u64, u64>>::ruling(1), Ok(false));
+            assert_ok!(Jury::set_jurors(Origin::ROOT, vec![8]));
+            assert_eq!(<Jury as Arbitrator<u64, u64>>::ruling(1), Ok(false));
+        });
+    }
+}
@@ -36,6 +36,9 @@ pub use timestamp::BlockPeriod;
 pub use support::{StorageValue, construct_runtime};
 
 
+pub mod api;
+mod jury;
+mod peg;
 mod tcr;
 mod token;
 
@@ -60,6 +63,9 @@ pub type BlockNumber = u64;
 /// Index of an account's extrinsic in the chain.
 pub type Nonce = u64;
 
+/// Identifier of an asset held in the token module.
+pub type AssetId = u32;
+
 
 
 /// Opaque types. These are used by the CLI to instantiate machinery that don't need to know
@@ -191,13 +197,46 @@ impl sudo::Trait for Runtime {
 	type Proposal = Call;
 }
 
+/// The asset created at genesis, which listings are staked in and fees are paid with.
+pub struct TcrToken;
+
+impl token::AssetIdProvider<AssetId> for TcrToken {
+	fn asset_id() -> AssetId {
+		0
+	}
+}
+
 impl tcr::Trait for Runtime {
 	type Event = Event;
+	/// Listings are staked in the TCR token rather than the native balance.
+	type Currency = token::AssetCurrency<Runtime, TcrToken>;
+	type BalanceHistory = token::AssetCurrency<Runtime, TcrToken>;
+	/// Challenges are decided by token-weighted polls; use `Jury` for a fixed jury instead.
+	type Arbitrator = Tcr;
+}
+
+impl jury::Trait for Runtime {
+	type Event = Event;
+	/// The jury is appointed by sudo.
+	type JuryOrigin = system::EnsureRoot<AccountId>;
 }
 
 impl token::Trait for Runtime {
 	type Event = Event;
 	type TokenBalance = u128;
+	type AssetId = AssetId;
+	/// Minting and burning on behalf of others is left to sudo.
+	type ManagerOrigin = system::EnsureRoot<AccountId>;
+	/// Freezing accounts and pausing assets is left to sudo as well.
+	type FreezeOrigin = system::EnsureRoot<AccountId>;
+	/// Vesting schedules are measured in blocks.
+	type VestingMoment = BlockNumber;
+	type VestingClock = System;
+}
+
+impl peg::Trait for Runtime {
+	type Event = Event;
+	type NativeCurrency = Balances;
 }
 
 construct_runtime!(
@@ -215,6 +254,8 @@ construct_runtime!(
 		Sudo: sudo,
 		Tcr: tcr::{Module, Call, Storage, Event<T>, Config<T>},
 		Token: token{Module, Call, Storage, Event<T>, Config<T>},
+		Peg: peg::{Module, Call, Storage, Event<T>, Config<T>},
+		Jury: jury::{Module, Call, Storage, Event<T>, Config<T>},
 	}
 );
 
@@ -232,8 +273,11 @@ pub type BlockId = generic::BlockId<Block>;
 pub type UncheckedExtrinsic = generic::UncheckedMortalCompactExtrinsic<Address, Nonce, Call, AccountSignature>;
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Nonce, Call>;
+/// Module transaction fees are charged in: `token::AssetCurrency` for a token asset, or
+/// `Balances` for the native currency.
+type FeePayment = token::AssetCurrency<Runtime, TcrToken>;
 /// Executive: handles dispatch to the various modules.
-pub type Executive = executive::Executive<Runtime, Block, Context, Balances, AllModules>;
+pub type Executive = executive::Executive<Runtime, Block, Context, FeePayment, AllModules>;
 
 // Implement our runtime API endpoints. This is just a bunch of proxying.
 impl_runtime_apis! {
@@ -256,6 +300,34 @@ impl_runtime_apis! {
 
 	}
 
+	impl api::TokenApi<Block, AssetId, u128> for Runtime {
+		fn metadata(asset_id: AssetId) -> token::TokenMetadata {
+			Token::metadata(asset_id)
+		}
+
+		fn total_supply(asset_id: AssetId) -> u128 {
+			Token::total_supply(asset_id)
+		}
+
+		fn audit(asset_id: AssetId) -> token::AuditReport<u128> {
+			Token::audit(asset_id)
+		}
+	}
+
+	impl api::TcrApi<Block, Hash, AccountId> for Runtime {
+		fn top_listings(count: u32) -> Vec<Hash> {
+			Tcr::top_listings(count)
+		}
+
+		fn listing_tier(listing_hash: Hash) -> Option<u8> {
+			Tcr::listing_tier(listing_hash)
+		}
+
+		fn poll_evidence(challenge_id: u32) -> Vec<(AccountId, Vec<u8>)> {
+			Tcr::evidence(challenge_id)
+		}
+	}
+
 	impl runtime_api::Metadata<Block> for Runtime {
 		fn metadata() -> OpaqueMetadata {
 			Runtime::metadata().into()
@@ -0,0 +1,331 @@
+use crate::token;
+use parity_codec_derive::{Decode,Encode};
+use rstd::prelude::*;
+use runtime_io;
+use runtime_primitives::traits::{CheckedAdd,CheckedDiv,CheckedMul,CheckedSub,EnsureOrigin,One,Zero};
+use support::{
+    decl_event,decl_module,decl_storage,dispatch::Result,
+    ensure,StorageValue};
+use support::traits::Currency;
+use system::ensure_signed;
+
+/// Seed of the reserve account, so that no key controls it.
+const MODULE_ID: &[u8; 8] = b"tcr/peg ";
+
+/// Exchanges native balance for TCR tokens and back.
+///
+/// Native balance paid for tokens is held by the reserve account until the tokens are sold
+/// back, so the reserve always covers the refund owed on every token this module has issued.
+pub trait Trait: token::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// The native currency; it must share the token's balance type.
+    type NativeCurrency: Currency<Self::AccountId, Balance = Self::TokenBalance>;
+}
+
+/// How the native price of a token moves with the number of tokens issued through the peg.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode,Decode,Clone,PartialEq,Eq)]
+pub enum BondingCurve<Balance> {
+    /// Every token costs the same amount of native balance.
+    Fixed(Balance),
+    /// The price starts at `base` and rises by one for every `step` tokens issued.
+    Linear { base: Balance, step: Balance },
+}
+
+impl<Balance: Zero> BondingCurve<Balance> {
+    /// A linear curve has to rise by at least one every so many tokens.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            BondingCurve::Fixed(_) => true,
+            BondingCurve::Linear { step, .. } => !step.is_zero(),
+        }
+    }
+}
+
+impl<Balance: One> Default for BondingCurve<Balance> {
+    fn default() -> Self {
+        BondingCurve::Fixed(One::one())
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Peg {
+        /// Token asset bought and sold through the peg.
+        Asset get(asset) config(): T::AssetId;
+        Curve get(curve) build(|config: &GenesisConfig<T>| {
+            assert!(config.curve.is_valid(), "Curve step cannot be zero.");
+            config.curve.clone()
+        }): BondingCurve<T::TokenBalance>;
+        /// Tokens issued through the peg and not yet sold back.
+        Issued get(issued): T::TokenBalance;
+    }
+    add_extra_genesis {
+        config(curve): BondingCurve<T::TokenBalance>;
+    }
+}
+
+decl_event!(
+    pub enum Event<T> where AccountId = <T as system::Trait>::AccountId,
+    Balance = <T as token::Trait>::TokenBalance {
+        /// Buyer, tokens received and native balance paid.
+        Bought(AccountId,Balance,Balance),
+        /// Seller, tokens burned and native balance returned.
+        Sold(AccountId,Balance,Balance),
+    }
+);
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
+        fn buy(origin, #[compact] amount: T::TokenBalance, #[compact] max_cost: T::TokenBalance) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            let issued = Self::issued();
+            let updated_issued = issued.checked_add(&amount)
+                .ok_or("overflow in calculating issued tokens")?;
+            let cost = Self::price_between(issued, updated_issued)?;
+            ensure!(cost <= max_cost, "Price is above the maximum cost.");
+            <token::Module<T>>::ensure_not_frozen(Self::asset(), &sender)?;
+            <token::Module<T>>::ensure_can_mint(Self::asset(), &sender, amount)?;
+
+            T::NativeCurrency::transfer(&sender, &Self::reserve_account(), cost)?;
+            <token::Module<T>>::_mint(Self::asset(), sender.clone(), amount)?;
+            <Issued<T>>::put(updated_issued);
+
+            Self::deposit_event(RawEvent::Bought(sender, amount, cost));
+            Ok(())
+        }
+
+        fn sell(origin, #[compact] amount: T::TokenBalance, #[compact] min_return: T::TokenBalance) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            <token::Module<T>>::ensure_can_burn(Self::asset(), &sender, amount)?;
+            let issued = Self::issued();
+            let updated_issued = issued.checked_sub(&amount)
+                .ok_or("Cannot sell more tokens than were issued through the peg.")?;
+            let refund = Self::price_between(updated_issued, issued)?;
+            ensure!(refund >= min_return, "Price is below the minimum return.");
+
+            T::NativeCurrency::transfer(&Self::reserve_account(), &sender, refund)?;
+            <token::Module<T>>::_burn(Self::asset(), sender.clone(), amount)?;
+            <Issued<T>>::put(updated_issued);
+
+            Self::deposit_event(RawEvent::Sold(sender, amount, refund));
+            Ok(())
+        }
+
+        fn set_curve(origin, curve: BondingCurve<T::TokenBalance>) -> Result {
+            T::ManagerOrigin::ensure_origin(origin)?;
+            ensure!(curve.is_valid(), "Curve step cannot be zero.");
+            ensure!(Self::issued().is_zero(),
+                "Curve cannot change while pegged tokens are outstanding.");
+
+            <Curve<T>>::put(curve);
+            Ok(())
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Account holding the native balance backing issued tokens. It is derived from the
+    /// module id, so only this module can move its funds.
+    pub fn reserve_account() -> T::AccountId {
+        let seed = runtime_io::blake2_256(MODULE_ID);
+        parity_codec::Decode::decode(&mut &seed[..]).unwrap_or_default()
+    }
+
+    /// Native balance currently backing the issued tokens.
+    pub fn reserve_balance() -> T::TokenBalance {
+        T::NativeCurrency::free_balance(&Self::reserve_account())
+    }
+
+    /// Native cost of moving the issued supply from `from` up to `to`.
+    fn price_between(from: T::TokenBalance, to: T::TokenBalance)
+        -> rstd::result::Result<T::TokenBalance, &'static str> {
+        // Taking the difference of the areas keeps rounding consistent however
+        // purchases and sales are split, so the reserve always covers the refunds.
+        let cost = Self::area_under_curve(to)?.checked_sub(&Self::area_under_curve(from)?)
+            .ok_or("overflow in calculating price")?;
+        Ok(cost)
+    }
+
+    /// Native balance needed to issue `supply` tokens starting from none.
+    fn area_under_curve(supply: T::TokenBalance) -> rstd::result::Result<T::TokenBalance, &'static str> {
+        match Self::curve() {
+            BondingCurve::Fixed(price) => supply.checked_mul(&price),
+            BondingCurve::Linear { base, step } => {
+                let two = T::TokenBalance::one() + T::TokenBalance::one();
+                let slope_area = supply.checked_mul(&supply)
+                    .and_then(|squared| step.checked_mul(&two)
+                        .and_then(|divisor| squared.checked_div(&divisor)));
+                supply.checked_mul(&base)
+                    .and_then(|base_area| slope_area.and_then(|slope_area| base_area.checked_add(&slope_area)))
+            },
+        }.ok_or("overflow in calculating price")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use primitives::{Blake2Hasher,H256};
+    use runtime_io::with_externalities;
+    use runtime_primitives::{
+        testing::{Digest,DigestItem,Header},
+        traits::{BlakeTwo256,IdentityLookup},
+        BuildStorage,
+    };
+    use support::{assert_noop,assert_ok,impl_outer_origin};
+    use support::traits::{LockableCurrency,WithdrawReasons};
+
+    impl_outer_origin!{
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<u64>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+    impl token::Trait for Test {
+        type Event = ();
+        type TokenBalance = u64;
+        type AssetId = u32;
+        type ManagerOrigin = system::EnsureRoot<u64>;
+        type FreezeOrigin = system::EnsureRoot<u64>;
+        type VestingMoment = u64;
+        type VestingClock = system::Module<Test>;
+    }
+    pub struct StakeAsset;
+    impl token::AssetIdProvider<u32> for StakeAsset {
+        fn asset_id() -> u32 {
+            0
+        }
+    }
+    /// A second asset stands in for the native currency the peg exchanges.
+    pub struct NativeAsset;
+    impl token::AssetIdProvider<u32> for NativeAsset {
+        fn asset_id() -> u32 {
+            1
+        }
+    }
+    impl Trait for Test {
+        type Event = ();
+        type NativeCurrency = Native;
+    }
+    type Peg = Module<Test>;
+    type Token = token::Module<Test>;
+    type Stake = token::AssetCurrency<Test, StakeAsset>;
+    type Native = token::AssetCurrency<Test, NativeAsset>;
+
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let mut t = system::GenesisConfig::<Test>::default()
+            .build_storage()
+            .unwrap()
+            .0;
+        t.extend(
+            token::GenesisConfig::<Test> {
+                owner: 1,
+                name: b"Curation Token".to_vec(),
+                symbol: b"CRT".to_vec(),
+                decimals: 12,
+                total_supply: 1000,
+                vesting: vec![],
+                stake_unvested: false,
+                transaction_base_fee: 0,
+                transaction_byte_fee: 0,
+                fee_account: 0,
+            }
+            .build_storage()
+            .unwrap()
+            .0,
+        );
+        t.extend(
+            GenesisConfig::<Test> {
+                asset: 0,
+                curve: BondingCurve::Fixed(2),
+            }
+            .build_storage()
+            .unwrap()
+            .0,
+        );
+        t.into()
+    }
+
+    #[test]
+    fn should_buy_and_sell_along_the_curve() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Token::create_asset(Origin::signed(2), 1000, b"Native".to_vec(), b"NAT".to_vec(), 0));
+            assert_noop!(Peg::set_curve(Origin::ROOT, BondingCurve::Linear { base: 1, step: 0 }),
+                "Curve step cannot be zero.");
+            assert_ok!(Peg::set_curve(Origin::ROOT, BondingCurve::Linear { base: 1, step: 10 }));
+
+            assert_noop!(Peg::buy(Origin::signed(2), 10, 14), "Price is above the maximum cost.");
+            assert_ok!(Peg::buy(Origin::signed(2), 10, 15));
+            assert_eq!(Native::free_balance(&2), 985);
+            assert_eq!(Peg::reserve_balance(), 15);
+            assert_ok!(Peg::buy(Origin::signed(2), 10, 25));
+            assert_eq!(Native::free_balance(&2), 960);
+            assert_eq!(Peg::reserve_balance(), 40);
+            assert_eq!(Token::balance_of((0, 2)), 20);
+            assert_eq!(Token::total_supply(0), 1020);
+
+            assert_noop!(Peg::sell(Origin::signed(2), 10, 26), "Price is below the minimum return.");
+            assert_ok!(Peg::sell(Origin::signed(2), 10, 25));
+            assert_eq!(Native::free_balance(&2), 985);
+            assert_eq!(Peg::reserve_balance(), 15);
+            assert_eq!(Peg::issued(), 10);
+            assert_noop!(Peg::sell(Origin::signed(2), 11, 0), "Not enough balance.");
+        });
+    }
+
+    #[test]
+    fn should_keep_native_balance_when_mint_fails() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Token::create_asset(Origin::signed(2), 1000, b"Native".to_vec(), b"NAT".to_vec(), 0));
+            assert_ok!(Token::mint(Origin::ROOT, 0, 3, u64::max_value() - 1000));
+
+            assert_noop!(Peg::buy(Origin::signed(2), 1, 2), "overflow in calculating total supply");
+            assert_eq!(Native::free_balance(&2), 1000);
+        });
+    }
+
+    #[test]
+    fn should_fail_trade_frozen_or_locked_tokens() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Token::create_asset(Origin::signed(2), 1000, b"Native".to_vec(), b"NAT".to_vec(), 0));
+            assert_ok!(Peg::buy(Origin::signed(2), 50, 100));
+            assert_eq!(Native::free_balance(&2), 900);
+
+            assert_ok!(Token::freeze_account(Origin::ROOT, 0, 2));
+            assert_noop!(Peg::buy(Origin::signed(2), 10, 20), "Account is frozen.");
+            assert_noop!(Peg::sell(Origin::signed(2), 50, 100), "Account is frozen.");
+            assert_ok!(Token::thaw_account(Origin::ROOT, 0, 2));
+
+            assert_ok!(Token::pause(Origin::ROOT, 0));
+            assert_noop!(Peg::sell(Origin::signed(2), 50, 100), "Asset is paused.");
+            assert_ok!(Token::unpause(Origin::ROOT, 0));
+
+            <Stake as LockableCurrency<u64>>::set_lock(*b"testlock", &2, 50, 10, WithdrawReasons::all());
+            assert_noop!(Peg::sell(Origin::signed(2), 1, 2),
+                "account liquidity restrictions prevent withdrawal");
+
+            system::Module::<Test>::set_block_number(10);
+            assert_ok!(Peg::sell(Origin::signed(2), 50, 100));
+            assert_eq!(Native::free_balance(&2), 1000);
+            assert_eq!(Peg::reserve_balance(), 0);
+        });
+    }
+}
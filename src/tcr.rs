@@ -1,15 +1,44 @@
-use crate::token;
-use parity_codec_derive::{Decodd,Encode};
+use parity_codec_derive::{Decode,Encode};
 use rstd::prelude::*;
 use runtime_io;
-use runtime_primitives::traits::{As,CheckedAdd,CheckedDiv,CheckedMul,Hash};
+use runtime_primitives::Permill;
+use runtime_primitives::traits::{As,CheckedAdd,CheckedDiv,CheckedMul,Hash,IntegerSquareRoot,
+    Saturating,SimpleArithmetic,Zero};
 use support::{
     decl_event,decl_module,decl_storage,dispatch::Result,
-    enusre,StorageMap,StorageValue};
+    ensure,StorageMap,StorageValue};
+use support::traits::{Currency,LockableCurrency,LockIdentifier,ReservableCurrency,WithdrawReasons};
 use {system::ensure_signed,timestamp};
+use crate::token::{BalanceHistory,TransferReserved};
 
-pub trait Trait: timestamp::Trait + token::Trait {
+const CONVICTION_LOCK: LockIdentifier = *b"tcrvotes";
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: timestamp::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// The currency listing, challenge and vote deposits are reserved in, and conviction
+    /// votes are locked in once they are settled.
+    type Currency: ReservableCurrency<Self::AccountId>
+        + LockableCurrency<Self::AccountId, Moment = Self::BlockNumber>
+        + TransferReserved<Self::AccountId, BalanceOf<Self>>;
+    /// Past balances of `Currency`, used when polls are weighed by snapshot.
+    type BalanceHistory: BalanceHistory<Self::AccountId, Self::BlockNumber, BalanceOf<Self>>;
+    /// Decides challenges; this module's own token-weighted polls unless the runtime
+    /// plugs in another arbitrator.
+    type Arbitrator: Arbitrator<Self::AccountId, Self::Moment>;
+}
+
+/// Decides whether a challenged listing stays in the registry. Disputes share their id with
+/// the challenge they decide.
+pub trait Arbitrator<AccountId, Moment> {
+    /// Starts deciding a challenge, with the ruling due once `ends` has passed.
+    fn create_dispute(dispute_id: u32, ends: Moment);
+    /// `true` if the listing should stay, or an error while the dispute is undecided.
+    fn ruling(dispute_id: u32) -> rstd::result::Result<bool, &'static str>;
+    /// Accepts `who`'s appeal of a decided dispute, or refuses it with an error. An accepted
+    /// appeal is decided in a new round, as a dispute of its own.
+    fn appeal(dispute_id: u32, who: &AccountId) -> Result;
 }
 
 #[cfg_attr(feature = "std", derive(Debug))]
@@ -21,67 +50,366 @@ pub struct Listing<U,V,W> {
     deposit: U,
     owner: V,
     application_expiry: W,
-    whiteListed: bool,
+    whitelisted: bool,
     challenge_id: u32,
+    category_id: u32,
+    /// Trust tier reached; every whitelisted listing starts in tier `0`.
+    tier: u8,
+}
+
+/// A stricter registry a whitelisted listing can be promoted into from the tier below.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode,Decode,Default,Clone,PartialEq)]
+pub struct Tier<U,W> {
+    name: Vec<u8>,
+    /// Extra deposit needed to apply for promotion into this tier.
+    min_deposit: U,
+    /// How long a promotion into this tier can be challenged for.
+    challenge_period: W,
+}
+
+/// A pending application to move a listing up one tier.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode,Decode,Default,Clone,PartialEq)]
+pub struct Promotion<U> {
+    tier: u8,
+    /// The extra deposit, which is all a challenge to the promotion puts at stake.
+    deposit: U,
+}
+
+/// A section of the registry with its own deposit and stage lengths.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode,Decode,Default,Clone,PartialEq)]
+pub struct Category<U,W> {
+    name: Vec<u8>,
+    /// Category this one is nested in; `0` is the root of the tree.
+    parent: u32,
+    /// Overrides inherited from the parent category, or the registry config at the root.
+    min_deposit: Option<U>,
+    apply_stage_len: Option<W>,
+    commit_stage_len: Option<W>,
+    /// Whether a listing can only be whitelisted while its parent listing in the parent
+    /// category is.
+    requires_parent: bool,
 }
 
 #[cfg_attr(feature = "std", derive(Debug))]
 #[derive(Encode,Decode,Default,Clone,PartialEq)]
 pub struct Challenge<T,U,V,W> {
     listing_hash: T,
+    listing_owner: V,
     deposit: U,
     owner: V,
     voting_ends: W,
     resolved: bool,
     reward_pool: U,
     total_tokens: U,
+    /// Why the listing was challenged, as one of the registry's published reason codes;
+    /// `0` when none was given.
+    reason: u8,
+    /// Round of the dispute this challenge decides, `1` for the original challenge. In an
+    /// appeal round `owner` is the appellant, and its deposit is the only one at stake.
+    round: u32,
+    /// Challenge whose ruling this round appeals, or `0` for the original challenge.
+    previous: u32,
+    /// In an appeal round, whether the appellant argued for keeping the listing.
+    appellant_supports: bool,
+    /// Whether the listing had to leave with its parent before the ruling could take effect,
+    /// in which case every deposit and vote is returned as it was.
+    voided: bool,
+}
+
+/// A challenge still collecting its deposit from several funders.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode,Decode,Default,Clone,PartialEq)]
+pub struct ChallengeFund<U,W> {
+    raised: U,
+    /// When contributions close; funders can be refunded after this if the deposit is short.
+    funding_ends: W,
 }
 
 #[cfg_attr(feature = "std", derive(Debug))]
 #[derive(Encode,Decode,Default,Clone,PartialEq)]
-pub struct Vote<U> {
+pub struct Vote<U,V> {
     value: bool,
     deposit: U,
     claimed: bool,
+    conviction: Conviction,
+    /// The delegate that cast this vote on the voter's behalf, if the voter did not vote itself.
+    delegate: Option<V>,
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode,Decode,Default,Clone,PartialEq)]
+pub struct Delegation<U,V> {
+    target: V,
+    /// Stake the delegate may vote with on each poll.
+    amount: U,
+    conviction: Conviction,
+}
+
+/// An account's stake on the rank of a whitelisted listing.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode,Decode,Default,Clone,PartialEq)]
+pub struct RankStake<U> {
+    /// Whether the stake pushes the listing up rather than down.
+    support: bool,
+    amount: U,
+}
+
+/// Total stake for and against a listing's rank.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode,Decode,Default,Clone,PartialEq)]
+pub struct RankTotals<U> {
+    staked_for: U,
+    staked_against: U,
 }
 
 #[cfg_attr(feature = "std",derive(Debug))]
 #[derive(Encode,Decode,Default,Clone,PartialEq)]
 pub struct Poll<T,U> {
     listing_hash: T,
+    /// Voting weight on each side: the stake, its square root in a quadratic poll, or a
+    /// head count when a jury decides.
     votes_for: U,
     votes_against: U,
+    /// Tokens staked on each side, which rewards are paid in proportion to.
+    stake_for: U,
+    stake_against: U,
     passed: bool,
-} 
+    mode: VotingMode,
+}
+
+/// Extra voting weight in return for keeping winning stake locked after it is claimed.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode,Decode,Clone,Copy,PartialEq,Eq)]
+pub enum Conviction {
+    /// No weight and not locked. The stake still earns its share of the rewards if it is
+    /// on the winning side.
+    None,
+    /// 1x weight, locked for one conviction lock period.
+    Locked1x,
+    /// 2x weight, locked for two periods.
+    Locked2x,
+    /// 3x weight, locked for four periods.
+    Locked3x,
+    /// 4x weight, locked for eight periods.
+    Locked4x,
+    /// 5x weight, locked for sixteen periods.
+    Locked5x,
+    /// 6x weight, locked for thirty-two periods.
+    Locked6x,
+}
+
+impl Default for Conviction {
+    fn default() -> Self {
+        Conviction::None
+    }
+}
+
+impl Conviction {
+    /// Number of conviction lock periods the stake stays locked for.
+    pub fn lock_periods(self) -> u64 {
+        match self {
+            Conviction::None => 0,
+            Conviction::Locked1x => 1,
+            Conviction::Locked2x => 2,
+            Conviction::Locked3x => 4,
+            Conviction::Locked4x => 8,
+            Conviction::Locked5x => 16,
+            Conviction::Locked6x => 32,
+        }
+    }
+
+    /// `weight` scaled by this conviction's multiplier.
+    pub fn votes<B: SimpleArithmetic + Copy>(self, weight: B) -> B {
+        let multiplier = match self {
+            Conviction::None => 0,
+            Conviction::Locked1x => 1,
+            Conviction::Locked2x => 2,
+            Conviction::Locked3x => 3,
+            Conviction::Locked4x => 4,
+            Conviction::Locked5x => 5,
+            Conviction::Locked6x => 6,
+        };
+        weight.saturating_mul(B::sa(multiplier))
+    }
+}
+
+/// How a poll turns staked tokens into voting weight.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode,Decode,Clone,Copy,PartialEq,Eq)]
+pub enum VotingMode {
+    /// One token, one vote.
+    Linear,
+    /// Weight is the integer square root of the stake, and only verified voters may vote.
+    Quadratic,
+}
+
+impl Default for VotingMode {
+    fn default() -> Self {
+        VotingMode::Linear
+    }
+}
 
 decl_storage! {
     trait Store for Module<T: Trait> as Tcr {
         Owner get(owner) config(): T::AccountId;
         Admins get(admins): map T::AccountId => bool;
-        MinDeposit get(min_deposit) config(): Option<T::TokenBalance>;
+        MinDeposit get(min_deposit) config(): Option<BalanceOf<T>>;
         ApplyStageLen get(apply_stage_len) config(): Option<T::Moment>;
         CommitStageLen get(commit_stage_len) config(): Option<T::Moment>;
-        Listings get(listings) : map T::Hash => Listing<T::TokenBalance,T::AccountId,T::Moment>;
+        /// Categories below the root category `0`, which has no entry of its own.
+        Categories get(category): map u32 => Option<Category<BalanceOf<T>, T::Moment>>;
+        CategoryCount get(category_count): u32;
+        /// Listing in the parent category a listing was proposed under.
+        ListingParent get(listing_parent): map T::Hash => Option<T::Hash>;
+        /// Tiers above the base registry, which is tier `0` and has no entry of its own.
+        Tiers get(tier): map u8 => Option<Tier<BalanceOf<T>, T::Moment>>;
+        Promotions get(promotion): map T::Hash => Option<Promotion<BalanceOf<T>>>;
+        /// Promotion deposit a listing staked for each tier it holds, which is what a challenge
+        /// to demote it out of that tier puts at stake.
+        TierDeposits get(tier_deposit): map (T::Hash, u8) => BalanceOf<T>;
+        /// Account a listing's owner has offered the listing to.
+        PendingTransfers get(pending_transfer): map T::Hash => Option<T::AccountId>;
+        /// Accounts other than the owner that have staked on a listing's deposit.
+        Backers get(backers): map T::Hash => Vec<T::AccountId>;
+        Backing get(backing): map (T::Hash, T::AccountId) => BalanceOf<T>;
+        /// Part of a listing's deposit put up by backers rather than the owner.
+        BackedTotal get(backed_total): map T::Hash => BalanceOf<T>;
+        /// Least amount a single `back_listing` call can add.
+        MinBacking get(min_backing) config(): BalanceOf<T>;
+        /// Most accounts that can back one listing, bounding the work of resolving it.
+        MaxBackers get(max_backers) config(): u32;
+        Listings get(listings) : map T::Hash => Listing<BalanceOf<T>,T::AccountId,T::Moment>;
         ListingCount get(listing_count): u32;
         ListingIndexHash get(index_hash): map u32 => T::Hash;
         PollNonce get(poll_nonce) config(): u32;
-        Challenges get(challenges): map u32 => Challenge<T::Hash, T::TokenBalance, 
+        /// Rounds a dispute can run to, counting the original challenge.
+        MaxRounds get(max_rounds) config(): u32;
+        /// How long after a round's voting ends its ruling can be appealed.
+        AppealPeriod get(appeal_period) config(): T::Moment;
+        /// Shares of a losing party's deposit that are forfeited; the rest is refunded when the
+        /// challenge is resolved. An applicant is rejected before it is first whitelisted.
+        RejectedApplicantSlash get(rejected_applicant_slash) config(): Permill = Permill::from_percent(100);
+        RemovedListingSlash get(removed_listing_slash) config(): Permill = Permill::from_percent(100);
+        ChallengerSlash get(challenger_slash) config(): Permill = Permill::from_percent(100);
+        /// Evidence hashes or URIs submitted on each challenge, with who submitted them.
+        Evidence get(evidence): map u32 => Vec<(T::AccountId, Vec<u8>)>;
+        EvidenceCount get(evidence_count): map (u32, T::AccountId) => u32;
+        /// Pieces of evidence each account can submit on a challenge.
+        MaxEvidence get(max_evidence) config(): u32;
+        /// How long a crowdfunded challenge can collect contributions for.
+        ChallengeFundingLen get(challenge_funding_len) config(): T::Moment;
+        ChallengeFunds get(challenge_fund): map T::Hash => Option<ChallengeFund<BalanceOf<T>, T::Moment>>;
+        /// Accounts that put up a listing's challenge deposit; the first one owns the challenge.
+        ChallengeFunders get(challenge_funders): map T::Hash => Vec<T::AccountId>;
+        ChallengeFunding get(challenge_funding): map (T::Hash, T::AccountId) => BalanceOf<T>;
+        /// Least amount a single `fund_challenge` call can contribute.
+        MinChallengeFunding get(min_challenge_funding) config(): BalanceOf<T>;
+        /// Most accounts that can fund one challenge, bounding the work of resolving it.
+        MaxChallengeFunders get(max_challenge_funders) config(): u32;
+        Challenges get(challenges): map u32 => Challenge<T::Hash, BalanceOf<T>, 
             T::AccountId, T::Moment>;
-        Polls get(polls): map u32 => Poll<T::Hash,T::TokenBalance>;
-        Votes get(votes): map (u32, T::AccountId) => Vote<T::TokenBalance>;
+        Polls get(polls): map u32 => Poll<T::Hash,BalanceOf<T>>;
+        Votes get(votes): map (u32, T::AccountId) => Vote<BalanceOf<T>, T::AccountId>;
+        Delegations get(delegation): map T::AccountId => Option<Delegation<BalanceOf<T>, T::AccountId>>;
+        /// Accounts currently delegating to each delegate.
+        Delegators get(delegators): map T::AccountId => Vec<T::AccountId>;
+        /// Most accounts that can delegate to one delegate, bounding the work of its votes.
+        MaxDelegators get(max_delegators) config(): u32;
+        /// Delegators whose stake a delegate cast on a poll.
+        DelegatedVotes get(delegated_votes): map (u32, T::AccountId) => Vec<T::AccountId>;
+        /// Whether new challenges cap each vote at the voter's balance when the challenge was made.
+        SnapshotVoting get(snapshot_voting) config(): bool;
+        /// Block whose starting balances cap the votes on a poll, if it is weighed by snapshot.
+        PollSnapshot get(poll_snapshot): map u32 => Option<T::BlockNumber>;
+        /// Voting mode new challenges are polled with.
+        VotingModeOf get(voting_mode): VotingMode;
+        /// Accounts an admin has confirmed belong to distinct people, for quadratic polls.
+        VerifiedVoters get(is_verified): map T::AccountId => bool;
+        /// Blocks a winning `Conviction::Locked1x` vote stays locked for after it is claimed.
+        ConvictionLockPeriod get(conviction_lock_period) config(): T::BlockNumber;
+        /// Whether whitelisted listings can be staked on to rank them.
+        GradedMode get(graded_mode): bool;
+        RankStakes get(rank_stake): map (T::Hash, T::AccountId) => Option<RankStake<BalanceOf<T>>>;
+        RankTotalsOf get(rank_totals): map T::Hash => RankTotals<BalanceOf<T>>;
+        /// Staked listings, highest net stake first, at most `MaxRanked` of them.
+        Ranking get(ranking): Vec<T::Hash>;
+        /// Listings kept in `Ranking`; those pushed below it drop out until staked on again.
+        MaxRanked get(max_ranked) config(): u32;
+        /// Blocks unstaked rank stake stays reserved for before it can be withdrawn.
+        UnstakeCooldown get(unstake_cooldown) config(): T::BlockNumber;
+        /// Unstaked rank stake waiting out the cool-down, with the block it can be withdrawn at.
+        Unbonding get(unbonding): map T::AccountId => Vec<(T::BlockNumber, BalanceOf<T>)>;
+        /// Jurors drawn for each new challenge; `0` leaves polls open to every token holder.
+        JurySize get(jury_size) config(): u32;
+        /// Taken from the stake of a drawn juror that does not vote, and shared among the
+        /// jurors that voted with the outcome.
+        JurorPenalty get(juror_penalty) config(): BalanceOf<T>;
+        JurorStakes get(juror_stake): map T::AccountId => BalanceOf<T>;
+        /// Least stake a juror needs to join the pool, and to be drawn.
+        MinJurorStake get(min_juror_stake) config(): BalanceOf<T>;
+        /// Most accounts the juror pool holds, bounding the work of drawing a jury.
+        MaxJurors get(max_jurors) config(): u32;
+        /// Accounts with juror stake, in the order they first staked.
+        JurorPool get(juror_pool): Vec<T::AccountId>;
+        /// Unresolved challenges each juror has been drawn for.
+        ActiveJuries get(active_juries): map T::AccountId => u32;
+        Juries get(jury): map u32 => Vec<T::AccountId>;
     }
 }
 
 decl_event!(
     pub enum Event<T> where AccountId = <T as system::Trait>::AccountId,
-    Balance = <T as token::Trait>::TokenBalance,
+    Balance = BalanceOf<T>,
     Hash = <T as system::Trait>::Hash{
         Proposed(AccountId,Hash,Balance),
+        /// New category and its parent.
+        CategoryAdded(u32,u32),
+        CategoryUpdated(u32),
+        /// Listing and the tier it applied for.
+        PromotionRequested(Hash,u8),
+        /// Listing and the tier it now holds.
+        Promoted(Hash,u8),
+        /// Listing and the tier it fell back to after a successful challenge.
+        Demoted(Hash,u8),
+        /// Listing and the tier a successful challenge kept it out of.
+        PromotionRejected(Hash,u8),
+        /// Listing and the account it was offered to.
+        ListingOffered(Hash,AccountId),
+        /// Listing, previous owner and new owner.
+        ListingTransferred(Hash,AccountId,AccountId),
+        /// Backer, listing and the amount added to its deposit.
+        Backed(AccountId,Hash,Balance),
+        Exited(Hash),
+        /// Funder, listing and the amount contributed towards challenging it.
+        ChallengeFunded(AccountId,Hash,Balance),
+        ChallengeRefunded(Hash),
         Challenged(AccountId, Hash, u32, Balance),
-        Voted(AccountId,u32,Balance),
+        /// Appellant, listing, the new round's challenge id and its deposit.
+        RoundAppealed(AccountId,Hash,u32,Balance),
+        Voted(AccountId,u32,Balance,Conviction),
         Resolved(Hash,u32),
-        Accepted(Hash,u32),
+        Accepted(Hash),
+        Rejected(Hash),
         Claimed(AccountId, u32),
+        /// Submitter, challenge and the evidence hash or URI.
+        EvidenceSubmitted(AccountId,u32,Vec<u8>),
+        /// Delegator, delegate and the stake delegated.
+        Delegated(AccountId,AccountId,Balance),
+        Undelegated(AccountId),
+        /// Staker, listing, whether the stake supports the listing, and the amount added.
+        RankStaked(AccountId,Hash,bool,Balance),
+        RankUnstaked(AccountId,Hash,Balance),
+        /// Account and the unstaked rank stake released to it.
+        Withdrawn(AccountId,Balance),
+        JurorStaked(AccountId,Balance),
+        JurorUnstaked(AccountId,Balance),
+        /// Challenge and the jurors drawn to decide it.
+        JuryDrawn(u32,Vec<AccountId>),
+        /// Juror, challenge and the stake taken for not voting.
+        JurorPenalized(AccountId,u32,Balance),
     }
 );
 
@@ -93,20 +421,34 @@ decl_module! {
             let sender = ensure_signed(origin)?;
             ensure!(sender == Self::owner(),
             "Only the owner set in genesis config can initialize the TCR");
-            <token::Module<T>>::init(sender.clone())?;
             <Admins<T>>::insert(sender,true);
         }
 
-        fn propose(origin, data: Vec<u8>, #[compact] deposit: T::TokenBalance) -> Result {
+        /// Propose a listing in `category_id`. `parent` names a listing in the parent category,
+        /// and is required in categories whose listings need a whitelisted parent.
+        fn propose(origin, category_id: u32, data: Vec<u8>, #[compact] deposit: BalanceOf<T>,
+            parent: Option<T::Hash>) -> Result {
             let sender = ensure_signed(origin)?;
 
-            ensure!(data.len() <= 256, "Listing data cannot be more thab 256 bytes");
+            ensure!(data.len() <= 256, "Listing data cannot be more than 256 bytes");
 
-            let min_deposit = Self::min_deposit().ok_or("Min deposit not set")?;
-            ensure!(deposit >= min_depoist, "deposit should be more than min_deposit");
+            ensure!(Self::category_exists(category_id), "Category not found.");
+            let category = Self::category(category_id).unwrap_or_default();
+            if let Some(parent) = parent {
+                ensure!(<Listings<T>>::exists(parent), "Parent listing not found.");
+                ensure!(Self::listings(parent).category_id == category.parent,
+                    "Parent listing is not in the parent category.");
+            } else {
+                ensure!(!category.requires_parent, "This category requires a parent listing.");
+            }
+
+            let min_deposit = Self::category_setting(category_id, |c| c.min_deposit.clone())
+                .or_else(Self::min_deposit).ok_or("Min deposit not set")?;
+            ensure!(deposit >= min_deposit, "deposit should be more than min_deposit");
 
             let now = <timestamp::Module<T>>::get();
-            let apply_stage_len = Self::apply_stage_len().ok_or("Apply stage length not set.")?;
+            let apply_stage_len = Self::category_setting(category_id, |c| c.apply_stage_len.clone())
+                .or_else(Self::apply_stage_len).ok_or("Apply stage length not set.")?;
             let app_exp = now.checked_add(&apply_stage_len).ok_or("Overflow when setting application expiry")?;
 
             let hashed = <T as system::Trait>::Hashing::hash(&data);
@@ -121,15 +463,20 @@ decl_module! {
                 whitelisted: false,
                 challenge_id: 0,
                 application_expiry: app_exp,
+                category_id,
+                tier: 0,
             };
 
             ensure!(!<Listings<T>>::exists(hashed), "Listing already exists");
 
-            <token::Module<T>>::lock(sender.clone(), deposit, hashed.clone())?;
+            T::Currency::reserve(&sender, deposit)?;
 
             <ListingCount<T>>::put(listing_id + 1);
             <Listings<T>>::insert(hashed,listing);
             <ListingIndexHash<T>>::insert(listing_id,hashed);
+            if let Some(parent) = parent {
+                <ListingParent<T>>::insert(hashed, parent);
+            }
 
             Self::deposit_event(RawEvent::Proposed(sender, hashed.clone(), deposit));
             runtime_io::print("Listing created!");
@@ -137,7 +484,8 @@ decl_module! {
             Ok(())
         }
 
-        fn challenge(origin,listing_id: u32, #[compact] deposit: T::TokenBalance) -> Result {
+        fn challenge(origin,listing_id: u32, #[compact] deposit: BalanceOf<T>, reason: u8,
+            evidence: Vec<u8>) -> Result {
             let sender = ensure_signed(origin)?;
 
             ensure!(<ListingIndexHash<T>>::exists(listing_id),
@@ -146,82 +494,197 @@ decl_module! {
             let listing_hash = Self::index_hash(listing_id);
             let listing = Self::listings(listing_hash);
 
-            ensure!(listing.challenge_id == 0, "Listing is already challenged.");
-            enusure!(listing.owner != sender, "You cannot challenge your own listings.");
-            ensure!(depsoit >= listing.deposit, "Not enough deposit to challenge.");
+            let (listing_stake, voting_exp) = Self::challenge_terms(&sender, listing_hash, &listing)?;
+            ensure!(Self::challenge_fund(listing_hash).is_none(),
+                "A challenge to this listing is already being funded.");
+            ensure!(deposit >= listing_stake, "Not enough deposit to challenge.");
+            ensure!(evidence.len() <= 256, "Evidence cannot be more than 256 bytes");
+            // The challenge is new, so its evidence is the first the challenger submits.
+            ensure!(evidence.is_empty() || Self::max_evidence() > 0,
+                "You cannot submit more evidence on this challenge.");
+
+            T::Currency::reserve(&sender, deposit)?;
+
+            let challenge_id = Self::open_challenge(sender.clone(), listing_hash, &listing,
+                listing_stake, deposit, voting_exp, reason);
+            if !evidence.is_empty() {
+                Self::store_evidence(challenge_id, sender, evidence);
+            }
+            runtime_io::print("Challenge created!");
 
-            let now = <timestamp::Module<T>>::get();
+            Ok(())
+        }
+
+        /// Contribute towards challenging a listing. The challenge opens once the contributions
+        /// cover the listing's deposit, and is owned by whoever contributed first. Funders give
+        /// their reasons with `submit_evidence`.
+        fn fund_challenge(origin, listing_id: u32, #[compact] amount: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
 
-            let commit_stage_len = Self::commit_stage_len.ok_or("Commit stage length not set.")?;
-            let voting_exp = now.checked_add(&commit_stage_len).
-                ok_or("Overflow when setting voting expiry")?;
+            ensure!(<ListingIndexHash<T>>::exists(listing_id), "Listing not found.");
+            let listing_hash = Self::index_hash(listing_id);
+            let listing = Self::listings(listing_hash);
 
-            ensure!(listing.application_expiry > now, "Apply stage length has passed.");
+            let (listing_stake, voting_exp) = Self::challenge_terms(&sender, listing_hash, &listing)?;
+            ensure!(!amount.is_zero() && amount >= Self::min_challenge_funding(),
+                "Contribution is below the minimum.");
+            let new_funder = !<ChallengeFunding<T>>::exists((listing_hash, sender.clone()));
+            ensure!(!new_funder
+                || Self::challenge_funders(listing_hash).len() < Self::max_challenge_funders() as usize,
+                "Challenge has too many funders.");
 
-            let challenge = Challenge {
-                listing_hash,
-                deposit,
-                owner: sender.clone(),
-                voting_ends: voting_exp,
-                resolved: false,
-                reward_pool: <T::TokenBalance as As<u64>>:sa(0),
-                total_tokens: <T::TokenBalance as As<u64>>::sa(0),
+            let now = <timestamp::Module<T>>::get();
+            let mut fund = match Self::challenge_fund(listing_hash) {
+                Some(fund) => {
+                    ensure!(fund.funding_ends >= now, "Challenge funding period has passed.");
+                    fund
+                },
+                None => ChallengeFund {
+                    raised: Zero::zero(),
+                    funding_ends: now.checked_add(&Self::challenge_funding_len())
+                        .ok_or("Overflow when setting funding expiry")?,
+                },
             };
+            fund.raised = fund.raised.checked_add(&amount)
+                .ok_or("overflow in calculating challenge deposit")?;
 
-            let poll = Poll {
-                listing_hash,
-                votes_for: listing.deposit,
-                votes_against: deposit,
-                passed: false,
-            };
+            T::Currency::reserve(&sender, amount)?;
 
-            <token::Module<T>>::lock(sender.clone(), deposit, listing_hash)?;
+            if new_funder {
+                <ChallengeFunders<T>>::mutate(listing_hash, |funders| funders.push(sender.clone()));
+            }
+            <ChallengeFunding<T>>::mutate((listing_hash, sender.clone()), |funding| *funding += amount);
+            Self::deposit_event(RawEvent::ChallengeFunded(sender, listing_hash, amount));
+
+            if fund.raised >= listing_stake {
+                <ChallengeFunds<T>>::remove(listing_hash);
+                let owner = Self::challenge_funders(listing_hash)[0].clone();
+                Self::open_challenge(owner, listing_hash, &listing, listing_stake, fund.raised,
+                    voting_exp, 0);
+            } else {
+                <ChallengeFunds<T>>::insert(listing_hash, fund);
+            }
 
-            let poll_nonce = <PollNonce<T>>::get();
-            <Challenges<T>>::insert(poll_nonce,challenge);
-            <Polls<T>>::insert(poll_nonce,poll);
+            Ok(())
+        }
 
-            <Listings<T>>::mutate(listing_hash, |listing| {
-                listing.challenge_id = poll_nonce;
-            });
+        /// Attach an evidence hash or URI to an open challenge. Open to either side of the
+        /// challenge and to anyone who has voted on it.
+        fn submit_evidence(origin, challenge_id: u32, evidence: Vec<u8>) -> Result {
+            let sender = ensure_signed(origin)?;
 
-            <PollNonce<T>>::put(poll_nonce + 1);
+            ensure!(<Challenges<T>>::exists(challenge_id), "Challenge not found");
+            let challenge = Self::challenges(challenge_id);
+            ensure!(challenge.resolved == false, "Challenge is already resolved.");
+
+            let listing_hash = challenge.listing_hash;
+            ensure!(sender == challenge.owner || sender == challenge.listing_owner
+                || <ChallengeFunding<T>>::exists((listing_hash, sender.clone()))
+                || <Backing<T>>::exists((listing_hash, sender.clone()))
+                || <Votes<T>>::exists((challenge_id, sender.clone())),
+                "Only parties to the challenge and its voters can submit evidence.");
+            ensure!(!evidence.is_empty(), "Evidence cannot be empty");
+            ensure!(evidence.len() <= 256, "Evidence cannot be more than 256 bytes");
+            ensure!(Self::evidence_count((challenge_id, sender.clone())) < Self::max_evidence(),
+                "You cannot submit more evidence on this challenge.");
+
+            Self::store_evidence(challenge_id, sender, evidence);
+            Ok(())
+        }
 
-            Self::deposit_event(RawEvent::Challenged(sender,listing_hash,poll_nonce,deposit));
-            runtime_io::print("Challenge created!");
+        /// Return the contributions to a challenge that was not fully funded in time.
+        fn refund_challenge(_origin, listing_id: u32) -> Result {
+            ensure!(<ListingIndexHash<T>>::exists(listing_id), "Listing not found.");
+            let listing_hash = Self::index_hash(listing_id);
+            let fund = Self::challenge_fund(listing_hash)
+                .ok_or("No challenge is being funded for this listing.")?;
+            ensure!(fund.funding_ends < <timestamp::Module<T>>::get(),
+                "Challenge funding period has not passed.");
 
+            Self::refund_challenge_fund(listing_hash);
             Ok(())
         }
 
-        fn vote(origin, challenge_id: u32, value: bool, #[compact] deposit: T::TokenBalance) -> Result {
+        fn vote(origin, challenge_id: u32, value: bool, #[compact] deposit: BalanceOf<T>,
+            conviction: Conviction) -> Result {
             let sender = ensure_signed(origin)?;
 
-            enusre!(<Challenges<T>>::exists(challenge_id), "Challenges does ot exists.");
+            ensure!(<Challenges<T>>::exists(challenge_id), "Challenges does ot exists.");
             let challenge = Self::challenges(challenge_id);
             ensure!(challenge.resolved == false, "Challenge is already resolved");
 
             let now = <timestamp::Module<T>>::get();
             ensure!(challenge.voting_ends > now, "Commit stage length has passed.");
 
-            <token::Module<T>>::lock(sender.clone(), deposit, challenge.listing_hash)?;
+            let jury = Self::jury(challenge_id);
+            ensure!(jury.is_empty() || jury.contains(&sender),
+                "Only drawn jurors can vote on this challenge.");
+
+            // A direct vote replaces any stake a delegate already cast on the voter's behalf.
+            let previous = if <Votes<T>>::exists((challenge_id, sender.clone())) {
+                let previous = Self::votes((challenge_id, sender.clone()));
+                ensure!(previous.delegate.is_some(), "You have already voted on this challenge.");
+                Some(previous)
+            } else {
+                None
+            };
+
+            // Tokens moved between accounts after the snapshot carry no weight, so the
+            // same stake cannot be counted twice.
+            if let Some(snapshot) = Self::poll_snapshot(challenge_id) {
+                ensure!(deposit <= T::BalanceHistory::balance_at(&sender, snapshot),
+                    "Vote exceeds balance held when the challenge was created.");
+            }
 
             let mut poll_instance = Self::polls(challenge_id);
-            match value {
-                true => poll_instance.votes_for += deposit,
-                false => poll_instance.votes_against += deposit,
+            // Splitting stake across accounts only pays off if each account is a separate voter.
+            ensure!(poll_instance.mode != VotingMode::Quadratic || Self::is_verified(&sender),
+                "Only verified voters can vote in quadratic polls.");
+
+            match previous {
+                Some(ref previous) => {
+                    if deposit > previous.deposit {
+                        T::Currency::reserve(&sender, deposit - previous.deposit)?;
+                    } else {
+                        T::Currency::unreserve(&sender, previous.deposit - deposit);
+                    }
+                    Self::remove_vote(&mut poll_instance, previous.value, previous.deposit,
+                        previous.conviction);
+                },
+                None => T::Currency::reserve(&sender, deposit)?,
+            }
+            if jury.is_empty() {
+                Self::add_vote(&mut poll_instance, value, deposit, conviction);
+            } else {
+                Self::add_juror_vote(&mut poll_instance, value, deposit);
+            }
+
+            if let Some(Vote { delegate: Some(delegate), .. }) = previous {
+                <DelegatedVotes<T>>::mutate((challenge_id, delegate), |delegators|
+                    delegators.retain(|delegator| delegator != &sender));
             }
 
             let vote_instance = Vote {
                 value,
                 deposit,
                 claimed: false,
+                conviction,
+                delegate: None,
             };
 
-            <Polls<T>>::mutate(challenge_id, |poll| *poll = poll_instance);
+            <Votes<T>>::insert((challenge_id,sender.clone()),vote_instance);
 
-            <Votes<T>>::insert(challenge_id,sender.clone(),vote_instance);
+            Self::deposit_event(RawEvent::Voted(sender.clone(),challenge_id,deposit,conviction));
+
+            // Jurors are drawn to judge in person, so they do not carry delegated stake.
+            if jury.is_empty() {
+                for delegator in Self::delegators(&sender) {
+                    Self::cast_delegated_vote(challenge_id, &mut poll_instance, &sender, delegator, value);
+                }
+            }
+
+            <Polls<T>>::insert(challenge_id, poll_instance);
 
-            Self::deposit_event(RawEvent::Voted(sender,challenge_id,deposit));
             runtime_io::print("Vote created!");
             Ok(())
         }
@@ -233,33 +696,251 @@ decl_module! {
             let listing = Self::listings(listing_hash);
 
             let now = <timestamp::Module<T>>::get();
-            let challenge;
-            let poll;
 
-            if listing.challenge_id > 0 {
-                challenge = Self::challenges(listing.challenge_id);
-                poll = Self::polls(listing.challenge_id);
+            let promotion = Self::promotion(listing_hash);
 
-                ensure!(challenge.voting_ends < now,
-                    "Commit stage lenght has not passed");
-            } else {
-                ensure!(listing.application_expiry < now, 
+            if listing.challenge_id == 0 || Self::challenges(listing.challenge_id).resolved {
+                if let Some(promotion) = promotion {
+                    ensure!(listing.application_expiry < now,
+                        "Promotion challenge period has not passed");
+
+                    Self::promote(listing_hash, promotion);
+                    return Ok(());
+                }
+
+                ensure!(listing.whitelisted == false, "Listing is already whitelisted.");
+                ensure!(listing.application_expiry < now,
                     "Apply stage length has not passed");
 
-                <Listings<T>>::mutate(listing_hash, |listing|
-                {
-                    listing.whitelisted = true;
+                // Nobody challenged the listing, so losing its parent only costs it its place.
+                if !Self::parent_whitelisted(listing_hash, &listing) {
+                    Self::refund_listing(listing_hash, &listing);
+                    Self::remove_listing(listing_id, listing_hash);
+                    Self::deposit_event(RawEvent::Rejected(listing_hash));
+                    return Ok(());
                 }
-            });
 
-            if whitelisted == true {
+                <Listings<T>>::mutate(listing_hash, |listing| listing.whitelisted = true);
+
                 Self::deposit_event(RawEvent::Accepted(listing_hash));
+                return Ok(());
+            }
+
+            let last_round_id = listing.challenge_id;
+            let last_round = Self::challenges(last_round_id);
+
+            // Only the last round's ruling counts, once it can no longer be appealed.
+            let passed = T::Arbitrator::ruling(last_round_id)?;
+            if last_round.round < Self::max_rounds() {
+                let appeal_ends = last_round.voting_ends.checked_add(&Self::appeal_period())
+                    .ok_or("Overflow when setting appeal expiry")?;
+                ensure!(appeal_ends < now, "Appeal period has not passed.");
+            }
+
+            let mut appeal_ids = Vec::new();
+            let mut challenge_id = last_round_id;
+            while Self::challenges(challenge_id).previous != 0 {
+                appeal_ids.push(challenge_id);
+                challenge_id = Self::challenges(challenge_id).previous;
+            }
+            let mut challenge = Self::challenges(challenge_id);
+            let mut poll = Self::polls(challenge_id);
+
+            // A listing whose parent has left the registry cannot stay, so a ruling to keep it
+            // cannot take effect. It stands as given, but nobody wins or loses by it.
+            if passed && promotion.is_none() && !Self::parent_whitelisted(listing_hash, &listing) {
+                Self::void_challenge(listing_hash, &listing, challenge_id, appeal_ids, passed)?;
+                Self::remove_listing(listing_id, listing_hash);
+                Self::deposit_event(RawEvent::Rejected(listing_hash));
+                Self::deposit_event(RawEvent::Resolved(listing_hash,last_round_id));
+                return Ok(());
+            }
+
+            poll.passed = passed;
+            // A challenge to a listing in a higher tier, outside a promotion, would demote it.
+            let demotion = promotion.is_none() && listing.tier > 0;
+            let listing_stake = Self::listing_stake(listing_hash, &listing, promotion.as_ref());
+            let listing_slash = if listing.whitelisted {
+                Self::removed_listing_slash()
+            } else {
+                Self::rejected_applicant_slash()
+            };
+            let challenger_slash = Self::challenger_slash();
+
+            // The winning party is paid like a voter holding its own deposit;
+            // the rest of the slashed deposit is left for the winning voters to claim.
+            let (winner, winner_stake, loser, reward_pool, total_tokens) = if poll.passed {
+                (listing.owner.clone(), listing_stake, challenge.owner.clone(),
+                    challenger_slash * challenge.deposit, poll.stake_for)
+            } else {
+                (challenge.owner.clone(), challenge.deposit, listing.owner.clone(),
+                    listing_slash * listing_stake, poll.stake_against)
+            };
+
+            let winner_reward = Self::reward_share(reward_pool, winner_stake, total_tokens)?;
+            let backers = if promotion.is_some() || demotion {
+                Vec::new()
+            } else {
+                Self::backers(listing_hash)
+            };
+            let funders = Self::challenge_funders(listing_hash);
+            if poll.passed {
+                // Refund the unslashed part of each contribution, and gather the slashed part of
+                // a crowdfunded deposit with the challenge owner, as voters claim from it.
+                let mut owner_refund = challenge.deposit - reward_pool;
+                for funder in funders.iter().filter(|funder| *funder != &challenge.owner) {
+                    let funding = Self::challenge_funding((listing_hash, funder.clone()));
+                    let slashed = challenger_slash * funding;
+                    T::Currency::slash_reserved_to(funder, &challenge.owner, slashed)?;
+                    T::Currency::unreserve(funder, funding - slashed);
+                    owner_refund = owner_refund.saturating_sub(funding - slashed);
+                }
+                T::Currency::unreserve(&challenge.owner, owner_refund);
+                // Backers share the listing side's reward in proportion to their stake.
+                let mut owner_reward = winner_reward;
+                for backer in &backers {
+                    let share = Self::reward_share(winner_reward,
+                        Self::backing((listing_hash, backer.clone())), listing_stake)?;
+                    Self::repatriate(&loser, backer, share)?;
+                    owner_reward -= share;
+                }
+                Self::repatriate(&loser, &winner, owner_reward)?;
+            } else {
+                // Every backer forfeits the same share of its stake as the owner. Gathering the
+                // slashed stake with the owner lets voters claim from one account.
+                let mut owner_refund = listing_stake - reward_pool;
+                for backer in &backers {
+                    let backing = Self::backing((listing_hash, backer.clone()));
+                    let slashed = listing_slash * backing;
+                    T::Currency::slash_reserved_to(backer, &listing.owner, slashed)?;
+                    T::Currency::unreserve(backer, backing - slashed);
+                    owner_refund = owner_refund.saturating_sub(backing - slashed);
+                }
+                T::Currency::unreserve(&listing.owner, owner_refund);
+                // Funders share the challenger's reward and get their contributions back.
+                let mut owner_reward = winner_reward;
+                let mut owner_deposit = challenge.deposit;
+                for funder in funders.iter().filter(|funder| *funder != &challenge.owner) {
+                    let funding = Self::challenge_funding((listing_hash, funder.clone()));
+                    let share = Self::reward_share(winner_reward, funding, challenge.deposit)?;
+                    Self::repatriate(&loser, funder, share)?;
+                    T::Currency::unreserve(funder, funding);
+                    owner_reward -= share;
+                    owner_deposit -= funding;
+                }
+                Self::repatriate(&loser, &winner, owner_reward)?;
+                T::Currency::unreserve(&challenge.owner, owner_deposit);
+            }
+            Self::clear_challenge_funders(listing_hash);
+
+            Self::settle_jury(challenge_id, poll.passed)?;
+            for appeal_id in appeal_ids {
+                Self::settle_appeal_round(appeal_id, poll.passed, &winner, listing_slash,
+                    challenger_slash)?;
+            }
+
+            challenge.reward_pool = reward_pool - winner_reward;
+            challenge.total_tokens = total_tokens - winner_stake;
+            challenge.resolved = true;
+
+            if poll.passed {
+                match promotion {
+                    Some(promotion) => Self::promote(listing_hash, promotion),
+                    None if demotion => (),
+                    None => {
+                        <Listings<T>>::mutate(listing_hash, |listing| listing.whitelisted = true);
+                        Self::deposit_event(RawEvent::Accepted(listing_hash));
+                    },
+                }
+            } else if let Some(promotion) = promotion {
+                // The promotion deposit went to the challenge; the listing keeps its tier.
+                <Promotions<T>>::remove(listing_hash);
+                Self::deposit_event(RawEvent::PromotionRejected(listing_hash, promotion.tier));
+            } else if demotion {
+                Self::demote(listing_hash, listing.tier);
             } else {
-                <token::Module<T>>::unlock(challenge.owner,challenge.deposit,kisting_hash)?;
+                Self::remove_listing(listing_id, listing_hash);
                 Self::deposit_event(RawEvent::Rejected(listing_hash));
             }
 
-            Self::deposit_event(RawEvent::Resolved(listing_hash,listing.challenge_id));
+            <Challenges<T>>::insert(challenge_id, challenge);
+            <Polls<T>>::insert(challenge_id, poll);
+
+            Self::deposit_event(RawEvent::Resolved(listing_hash,last_round_id));
+            Ok(())
+        }
+
+        /// Appeal the ruling on a listing's latest round to a new round, decided by a jury
+        /// twice the size when juries are drawn. The appellant takes the side the ruling went
+        /// against and alone puts up a deposit, at least double the last round's. The arbitrator
+        /// can refuse the appeal.
+        fn appeal_round(origin, listing_id: u32, #[compact] deposit: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(<ListingIndexHash<T>>::exists(listing_id), "Listing not found.");
+            let listing_hash = Self::index_hash(listing_id);
+            let listing = Self::listings(listing_hash);
+            ensure!(listing.challenge_id != 0, "Listing has not been challenged.");
+
+            let previous_id = listing.challenge_id;
+            let previous = Self::challenges(previous_id);
+            ensure!(previous.resolved == false, "Challenge is already resolved.");
+            ensure!(previous.round < Self::max_rounds(), "No appeal rounds remain.");
+            let ruling = T::Arbitrator::ruling(previous_id)?;
+
+            let now = <timestamp::Module<T>>::get();
+            let appeal_ends = previous.voting_ends.checked_add(&Self::appeal_period())
+                .ok_or("Overflow when setting appeal expiry")?;
+            ensure!(appeal_ends >= now, "Appeal period has passed.");
+            let min_deposit = previous.deposit.checked_add(&previous.deposit)
+                .ok_or("overflow in calculating appeal deposit")?;
+            ensure!(deposit >= min_deposit, "Appeal deposit must be at least double the last round's.");
+
+            let commit_stage_len = Self::category_setting(listing.category_id, |c| c.commit_stage_len.clone())
+                .or_else(Self::commit_stage_len).ok_or("Commit stage length not set.")?;
+            let voting_exp = now.checked_add(&commit_stage_len)
+                .ok_or("Overflow when setting voting expiry")?;
+
+            ensure!(T::Currency::can_reserve(&sender, deposit), "Not enough balance for the appeal deposit.");
+
+            T::Arbitrator::appeal(previous_id, &sender)?;
+            T::Currency::reserve(&sender, deposit)?;
+
+            let appellant_supports = !ruling;
+            let challenge = Challenge {
+                listing_hash,
+                listing_owner: listing.owner.clone(),
+                deposit,
+                owner: sender.clone(),
+                voting_ends: voting_exp,
+                resolved: false,
+                reward_pool: Zero::zero(),
+                total_tokens: Zero::zero(),
+                reason: previous.reason,
+                round: previous.round + 1,
+                previous: previous_id,
+                appellant_supports,
+                voided: false,
+            };
+            let mode = Self::voting_mode();
+            let mut poll = Poll {
+                listing_hash,
+                votes_for: Zero::zero(),
+                votes_against: Zero::zero(),
+                stake_for: Zero::zero(),
+                stake_against: Zero::zero(),
+                passed: false,
+                mode,
+            };
+            // The deposit earns the appellant a share of the rewards, not votes; otherwise a
+            // large enough deposit would buy the outcome of the round.
+            match appellant_supports {
+                true => poll.stake_for = deposit,
+                false => poll.stake_against = deposit,
+            }
+
+            let challenge_id = Self::open_round(challenge, poll);
+            Self::deposit_event(RawEvent::RoundAppealed(sender, listing_hash, challenge_id, deposit));
             Ok(())
         }
 
@@ -270,35 +951,69 @@ decl_module! {
             let challenge = Self::challenges(challenge_id);
             ensure!(challenge.resolved == true, "Challenge is not resolved.");
 
+            ensure!(<Votes<T>>::exists((challenge_id,sender.clone())),
+                "You have not voted on this challenge.");
             let poll = Self::polls(challenge_id);
             let vote = Self::votes((challenge_id,sender.clone()));
 
             ensure!(vote.claimed == false, 
                 "Vote reward has already been claimed.");
 
-            if poll.passed = vote.value {
-                let reward_ratio = challenge.reward_pool.
-                    checked_div(&challenge.total_tokens).
-                    ok_or("Oveflow in calculating reward")?;
+            Self::settle_vote(challenge_id, &challenge, poll.passed, sender.clone(), vote)?;
 
-                let reward = reward_ratio.checked_mul(&vote.deposit).
-                    ok_or("overflow in calculating reward")?;
-                
-                let total = reward.checked_add(&vote.deposit).
-                    ok_or("overflow in calculating reward")?;
+            // A delegate settles the stake it cast for its delegators along with its own.
+            // Settlement fails before writing anything, so a delegator whose stake cannot be
+            // settled here is left to claim for itself.
+            for delegator in Self::delegated_votes((challenge_id, sender)) {
+                let vote = Self::votes((challenge_id, delegator.clone()));
+                if !vote.claimed {
+                    let _ = Self::settle_vote(challenge_id, &challenge, poll.passed, delegator, vote);
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Let `to` vote with up to `amount` of the caller's tokens, at the caller's `conviction`,
+        /// on polls the caller does not vote on.
+        fn delegate(origin, to: T::AccountId, #[compact] amount: BalanceOf<T>,
+            conviction: Conviction) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(to != sender, "You cannot delegate to yourself.");
 
-                <token::Module<T>>::unlock(sender.clone(),total,callenge.listing_hash)?;
+            let previous = Self::delegation(&sender);
+            if previous.as_ref().map_or(true, |previous| previous.target != to) {
+                ensure!(Self::delegators(&to).len() < Self::max_delegators() as usize,
+                    "Delegate has too many delegators.");
+            }
 
-                Self::deposit_event(RawEvent::Claimed(sender.clone(),challenge_id));
+            if let Some(previous) = previous {
+                <Delegators<T>>::mutate(previous.target, |delegators|
+                    delegators.retain(|delegator| delegator != &sender));
             }
 
-            <Votes<T>>::mutate((challenge_id,sender), |vote| vote.claimed = true);
+            <Delegations<T>>::insert(&sender, Delegation { target: to.clone(), amount, conviction });
+            <Delegators<T>>::mutate(&to, |delegators| delegators.push(sender.clone()));
+
+            Self::deposit_event(RawEvent::Delegated(sender, to, amount));
+            Ok(())
+        }
+
+        /// Stop delegating. Votes a delegate already cast stay in place.
+        fn undelegate(origin) -> Result {
+            let sender = ensure_signed(origin)?;
+            let delegation = Self::delegation(&sender).ok_or("You have not delegated your vote.")?;
+
+            <Delegators<T>>::mutate(delegation.target, |delegators|
+                delegators.retain(|delegator| delegator != &sender));
+            <Delegations<T>>::remove(&sender);
 
+            Self::deposit_event(RawEvent::Undelegated(sender));
             Ok(())
         }
 
         fn set_config(origin,
-            min_deposit: T::TokenBalance,
+            min_deposit: BalanceOf<T>,
             apply_stage_len: T::Moment,
             commit_stage_len: T::Moment) -> Result {
                 
@@ -311,92 +1026,1110 @@ decl_module! {
             Ok(())
         }
 
-        fn add_admin(origin new_admin: T::AccountId) -> Result {
+        fn add_category(origin, parent: u32, name: Vec<u8>,
+            min_deposit: Option<BalanceOf<T>>,
+            apply_stage_len: Option<T::Moment>,
+            commit_stage_len: Option<T::Moment>,
+            requires_parent: bool) -> Result {
             Self::ensure_admin(origin)?;
+            ensure!(name.len() <= 64, "Category name cannot be more than 64 bytes");
+            ensure!(Self::category_exists(parent), "Parent category not found.");
+
+            let category_id = Self::category_count().checked_add(1)
+                .ok_or("No new categories can be added.")?;
+            <Categories<T>>::insert(category_id, Category {
+                name,
+                parent,
+                min_deposit,
+                apply_stage_len,
+                commit_stage_len,
+                requires_parent,
+            });
+            <CategoryCount<T>>::put(category_id);
 
-            <Admins<T>>::insert(new_admin,ture);
-            runtime_io::print("New admin added!");
+            Self::deposit_event(RawEvent::CategoryAdded(category_id, parent));
             Ok(())
         }
 
-        fn remove_admin(origin, admin_to_remove: T::AccountId) -> Result {
+        /// Change a category's overrides. Listings already proposed keep their stage deadlines.
+        fn set_category_config(origin, category_id: u32,
+            min_deposit: Option<BalanceOf<T>>,
+            apply_stage_len: Option<T::Moment>,
+            commit_stage_len: Option<T::Moment>,
+            requires_parent: bool) -> Result {
             Self::ensure_admin(origin)?;
+            let mut category = Self::category(category_id).ok_or("Category not found.")?;
 
-            ensure!(Admins<T>>::exists(&admin_to_remove),
-                "The admin you are trying to remove does not exists");
+            category.min_deposit = min_deposit;
+            category.apply_stage_len = apply_stage_len;
+            category.commit_stage_len = commit_stage_len;
+            category.requires_parent = requires_parent;
+            <Categories<T>>::insert(category_id, category);
 
-            <Admins<T>>::remove(admin_to_remove);
-            runtime_io::print("Admin removed!");
+            Self::deposit_event(RawEvent::CategoryUpdated(category_id));
             Ok(())
         }
 
-    }
-}
+        /// Add to another account's listing deposit. The stake counts towards the listing's side of
+        /// an open challenge, and stays reserved until the listing exits or is rejected.
+        fn back_listing(origin, listing_id: u32, #[compact] amount: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
 
-impl<T: Trait> Module<T> {
-    fn ensure_admin(origin: T::Origin) -> Result {
-        let sender = ensure_signed(origin)?;
+            ensure!(<ListingIndexHash<T>>::exists(listing_id), "Listing not found.");
+            let listing_hash = Self::index_hash(listing_id);
+            let listing = Self::listings(listing_hash);
+            ensure!(listing.owner != sender, "You cannot back your own listing.");
+            ensure!(!amount.is_zero() && amount >= Self::min_backing(), "Backing is below the minimum.");
+            let new_backer = !<Backing<T>>::exists((listing_hash, sender.clone()));
+            ensure!(!new_backer || Self::backers(listing_hash).len() < Self::max_backers() as usize,
+                "Listing has too many backers.");
+            ensure!(Self::promotion(listing_hash).is_none(),
+                "Listing cannot be backed while it is applying for promotion.");
+            let deposit = listing.deposit.checked_add(&amount)
+                .ok_or("overflow in calculating deposit")?;
+            let backing = Self::backing((listing_hash, sender.clone()));
+            let total_backing = backing.checked_add(&amount)
+                .ok_or("overflow in calculating backing")?;
+            let challenge = Self::challenges(listing.challenge_id);
+            let challenged = listing.challenge_id != 0 && !challenge.resolved;
+            if challenged {
+                // Backing is a vote for the listing, so it only counts in the original round,
+                // where the listing's whole deposit is at stake, and while anyone may still vote.
+                ensure!(challenge.round == 1,
+                    "Listing cannot be backed while a ruling on it is appealed.");
+                ensure!(listing.tier == 0,
+                    "Listing cannot be backed while a challenge to its tier is open.");
+                ensure!(challenge.voting_ends > <timestamp::Module<T>>::get(),
+                    "Listing cannot be backed once voting on its challenge has closed.");
+                ensure!(Self::jury(listing.challenge_id).is_empty(),
+                    "Listing cannot be backed while a jury decides its challenge.");
+                // Backing during a challenge is held to the same rules as a vote.
+                if let Some(snapshot) = Self::poll_snapshot(listing.challenge_id) {
+                    ensure!(total_backing <= T::BalanceHistory::balance_at(&sender, snapshot),
+                        "Backing exceeds balance held when the challenge was created.");
+                }
+                ensure!(Self::polls(listing.challenge_id).mode != VotingMode::Quadratic
+                    || Self::is_verified(&sender),
+                    "Only verified voters can back listings in quadratic polls.");
+            }
 
-        ensure!(<Admins<T>>::exists(&sender),
-            "Access denied. Admin only.");
-        ensure!(Self::admins(sender) == true,
-            "Admin is not active");
+            T::Currency::reserve(&sender, amount)?;
 
-        Ok(())
-    }
-}
+            if challenged {
+                // The backer's weight is taken from its whole backing, so splitting it across
+                // calls gains nothing in a quadratic poll.
+                <Polls<T>>::mutate(listing.challenge_id, |poll| {
+                    poll.votes_for += Self::vote_weight(poll.mode, total_backing)
+                        - Self::vote_weight(poll.mode, backing);
+                    poll.stake_for += amount;
+                });
+            }
+            if new_backer {
+                <Backers<T>>::mutate(listing_hash, |backers| backers.push(sender.clone()));
+            }
+            <Backing<T>>::insert((listing_hash, sender.clone()), total_backing);
+            <BackedTotal<T>>::mutate(listing_hash, |total| *total += amount);
+            <Listings<T>>::mutate(listing_hash, |listing| listing.deposit = deposit);
 
+            Self::deposit_event(RawEvent::Backed(sender, listing_hash, amount));
+            Ok(())
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        /// Withdraw a listing from the registry, refunding its owner and every backer.
+        fn exit(origin, listing_id: u32) -> Result {
+            let sender = ensure_signed(origin)?;
 
-    use primitives::{Blake2Hasher,H256};
-    use runtime_io::with_externalities;
-    use runtimes_primitives::{
-        testing::{Digest,DigestItem,Header,UintAuthorityId},
-        traits::{BlackTwo256, IdentityLookup},
-        BuildStorage,
-    };
-    use support::{assert_noop,assert_ok,impl_outer_origin};
+            ensure!(<ListingIndexHash<T>>::exists(listing_id), "Listing not found.");
+            let listing_hash = Self::index_hash(listing_id);
+            let listing = Self::listings(listing_hash);
+            ensure!(listing.owner == sender, "Only the listing owner can exit.");
+            ensure!(listing.challenge_id == 0 || Self::challenges(listing.challenge_id).resolved,
+                "Listing cannot exit while it is challenged.");
+            ensure!(Self::promotion(listing_hash).is_none(),
+                "Listing cannot exit while it is applying for promotion.");
+            ensure!(Self::challenge_fund(listing_hash).is_none(),
+                "Listing cannot exit while a challenge to it is being funded.");
+
+            Self::refund_listing(listing_hash, &listing);
+            Self::remove_listing(listing_id, listing_hash);
+
+            Self::deposit_event(RawEvent::Exited(listing_hash));
+            Ok(())
+        }
 
-    impl_outer_origin!{
-        pub enum Origin for Test {}
-    }
+        /// Offer a listing to `new_owner`, who takes it over with `accept_listing`.
+        fn transfer_listing(origin, listing_id: u32, new_owner: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
 
-    #[derive(Clone, Eq, PartialEq)]
-    pub struct Test;
-    impl system::Trait for Test {
-        type Origin = Origin;
-        type Index = u64;
-        type BlockNumber = u64;
-        type Hash = H256;
-        type Hashing = BlakeTwo256;
-        type Digest = Digest;
-        type AccountId = u64;
-        type Lookup = IdentityLookup<u64>;
-        type Header = Header;
-        type Event = ();
-        type Log = DigestItem;
-    }
-    impl consensus::Trait for Test {
-        type Log = DigestItem;
-        type SessionKey = UintAuthorityId;
-        type InherentOfflineReport = ();
-    }
-    impl token::Trait for Test {
-        type Event = ();
-        type TokenBalance = u64; 
-    }
-    impl timestamp::Trait for Test {
-        type Moment = u64;
-        type OnTimestampSet = ();
-    }
-    impl Trait for Test {
-        type Event = ();
-    }
-    type Tcr = Module<Test>;
+            ensure!(<ListingIndexHash<T>>::exists(listing_id), "Listing not found.");
+            let listing_hash = Self::index_hash(listing_id);
+            let listing = Self::listings(listing_hash);
+            ensure!(listing.owner == sender, "Only the listing owner can transfer it.");
+            ensure!(new_owner != sender, "Listing already belongs to this account.");
+            Self::ensure_transferable(listing_hash, &listing)?;
+
+            <PendingTransfers<T>>::insert(listing_hash, new_owner.clone());
+
+            Self::deposit_event(RawEvent::ListingOffered(listing_hash, new_owner));
+            Ok(())
+        }
+
+        /// Take over a listing offered to the caller, along with its reserved deposit.
+        fn accept_listing(origin, listing_id: u32) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(<ListingIndexHash<T>>::exists(listing_id), "Listing not found.");
+            let listing_hash = Self::index_hash(listing_id);
+            ensure!(Self::pending_transfer(listing_hash) == Some(sender.clone()),
+                "Listing has not been offered to you.");
+            let listing = Self::listings(listing_hash);
+            Self::ensure_transferable(listing_hash, &listing)?;
+
+            let owner_stake = listing.deposit - Self::backed_total(listing_hash);
+            T::Currency::transfer_reserved(&listing.owner, &sender, owner_stake)?;
+
+            <PendingTransfers<T>>::remove(listing_hash);
+            <Listings<T>>::mutate(listing_hash, |listing| listing.owner = sender.clone());
+
+            Self::deposit_event(RawEvent::ListingTransferred(listing_hash, listing.owner, sender));
+            Ok(())
+        }
+
+        /// Apply to move a whitelisted listing up one tier. The extra `deposit` is reserved and
+        /// can be challenged for the next tier's challenge period.
+        fn request_promotion(origin, listing_id: u32, #[compact] deposit: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(<ListingIndexHash<T>>::exists(listing_id), "Listing not found.");
+            let listing_hash = Self::index_hash(listing_id);
+            let listing = Self::listings(listing_hash);
+            ensure!(listing.owner == sender, "Only the listing owner can request a promotion.");
+            ensure!(listing.whitelisted, "Only whitelisted listings can be promoted.");
+            ensure!(listing.challenge_id == 0 || Self::challenges(listing.challenge_id).resolved,
+                "Listing is already challenged.");
+            ensure!(Self::promotion(listing_hash).is_none(), "Listing is already applying for promotion.");
+
+            let next_tier = listing.tier.checked_add(1).ok_or("Listing is in the top tier.")?;
+            let tier = Self::tier(next_tier).ok_or("Listing is in the top tier.")?;
+            ensure!(deposit >= tier.min_deposit, "deposit should be more than min_deposit");
+            let expiry = <timestamp::Module<T>>::get().checked_add(&tier.challenge_period)
+                .ok_or("Overflow when setting application expiry")?;
+
+            T::Currency::reserve(&sender, deposit)?;
+
+            <Listings<T>>::mutate(listing_hash, |listing| listing.application_expiry = expiry);
+            <Promotions<T>>::insert(listing_hash, Promotion { tier: next_tier, deposit });
+
+            Self::deposit_event(RawEvent::PromotionRequested(listing_hash, next_tier));
+            Ok(())
+        }
+
+        /// Configure tier `tier`, which must be above the base registry and no more than one
+        /// above the highest configured tier.
+        fn set_tier(origin, tier: u8, name: Vec<u8>, #[compact] min_deposit: BalanceOf<T>,
+            challenge_period: T::Moment) -> Result {
+            Self::ensure_admin(origin)?;
+            ensure!(tier > 0, "Tier 0 is configured by set_config.");
+            ensure!(tier == 1 || <Tiers<T>>::exists(tier - 1), "Tiers must be added in order.");
+            ensure!(name.len() <= 64, "Tier name cannot be more than 64 bytes");
+
+            <Tiers<T>>::insert(tier, Tier { name, min_deposit, challenge_period });
+            Ok(())
+        }
+
+        /// Stake on a whitelisted listing's rank; `support` pushes it up, otherwise down.
+        fn stake_rank(origin, listing_id: u32, support: bool, #[compact] amount: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::graded_mode(), "Registry is not graded.");
+
+            ensure!(<ListingIndexHash<T>>::exists(listing_id), "Listing not found.");
+            let listing_hash = Self::index_hash(listing_id);
+            ensure!(Self::listings(listing_hash).whitelisted, "Only whitelisted listings can be ranked.");
+
+            let mut position = Self::rank_stake((listing_hash, sender.clone()))
+                .unwrap_or(RankStake { support, amount: Zero::zero() });
+            ensure!(position.support == support,
+                "Unstake before staking on the other side of a listing.");
+            position.amount = position.amount.checked_add(&amount)
+                .ok_or("overflow in calculating stake")?;
+
+            T::Currency::reserve(&sender, amount)?;
+
+            <RankStakes<T>>::insert((listing_hash, sender.clone()), position);
+            <RankTotalsOf<T>>::mutate(listing_hash, |totals| match support {
+                true => totals.staked_for += amount,
+                false => totals.staked_against += amount,
+            });
+            Self::rerank(listing_hash);
+
+            Self::deposit_event(RawEvent::RankStaked(sender, listing_hash, support, amount));
+            Ok(())
+        }
+
+        /// Take back all of the caller's stake on a listing's rank. The rank changes at once,
+        /// but the stake stays reserved until `UnstakeCooldown` blocks have passed.
+        ///
+        /// Takes the listing hash because rejected listings no longer have an index.
+        fn unstake_rank(origin, listing_hash: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            let position = Self::rank_stake((listing_hash, sender.clone()))
+                .ok_or("You have no stake on this listing.")?;
+
+            let release_at = <system::Module<T>>::block_number()
+                .saturating_add(Self::unstake_cooldown());
+
+            <RankStakes<T>>::remove((listing_hash, sender.clone()));
+            <RankTotalsOf<T>>::mutate(listing_hash, |totals| match position.support {
+                true => totals.staked_for -= position.amount,
+                false => totals.staked_against -= position.amount,
+            });
+            <Unbonding<T>>::mutate(&sender, |chunks| chunks.push((release_at, position.amount)));
+            Self::rerank(listing_hash);
+
+            Self::deposit_event(RawEvent::RankUnstaked(sender, listing_hash, position.amount));
+            Ok(())
+        }
+
+        /// Release unstaked rank stake whose cool-down has passed.
+        fn withdraw_unstaked(origin) -> Result {
+            let sender = ensure_signed(origin)?;
+            let now = <system::Module<T>>::block_number();
+
+            let mut released = <BalanceOf<T>>::zero();
+            let mut chunks = Self::unbonding(&sender);
+            chunks.retain(|&(release_at, amount)| if release_at <= now {
+                released += amount;
+                false
+            } else {
+                true
+            });
+            ensure!(!released.is_zero(), "No unstaked funds are ready to withdraw.");
+
+            if chunks.is_empty() {
+                <Unbonding<T>>::remove(&sender);
+            } else {
+                <Unbonding<T>>::insert(&sender, chunks);
+            }
+            T::Currency::unreserve(&sender, released);
+
+            Self::deposit_event(RawEvent::Withdrawn(sender, released));
+            Ok(())
+        }
+
+        /// Stake to be drawn as a juror, with odds proportional to the stake.
+        fn stake_juror(origin, #[compact] amount: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!amount.is_zero(), "Juror stake cannot be zero.");
+            let stake = Self::juror_stake(&sender).checked_add(&amount)
+                .ok_or("overflow in calculating juror stake")?;
+            ensure!(stake >= Self::min_juror_stake(), "Juror stake is below the minimum.");
+            let new_juror = !<JurorStakes<T>>::exists(&sender);
+            ensure!(!new_juror || Self::juror_pool().len() < Self::max_jurors() as usize,
+                "Juror pool is full.");
+
+            T::Currency::reserve(&sender, amount)?;
+
+            if new_juror {
+                <JurorPool<T>>::mutate(|pool| pool.push(sender.clone()));
+            }
+            <JurorStakes<T>>::mutate(&sender, |stake| *stake += amount);
+
+            Self::deposit_event(RawEvent::JurorStaked(sender, amount));
+            Ok(())
+        }
+
+        fn unstake_juror(origin, #[compact] amount: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::active_juries(&sender) == 0,
+                "Jurors cannot unstake while drawn for an unresolved challenge.");
+            let stake = Self::juror_stake(&sender);
+            ensure!(amount <= stake, "Not enough juror stake.");
+            ensure!(amount == stake || stake - amount >= Self::min_juror_stake(),
+                "Juror stake cannot fall below the minimum.");
+
+            T::Currency::unreserve(&sender, amount);
+            Self::set_juror_stake(&sender, stake - amount);
+
+            Self::deposit_event(RawEvent::JurorUnstaked(sender, amount));
+            Ok(())
+        }
+
+        fn set_appeal_config(origin, max_rounds: u32, appeal_period: T::Moment) -> Result {
+            Self::ensure_admin(origin)?;
+            ensure!(max_rounds > 0, "A dispute needs at least one round.");
+
+            <MaxRounds<T>>::put(max_rounds);
+            <AppealPeriod<T>>::put(appeal_period);
+            Ok(())
+        }
+
+        fn set_slash_rates(origin, rejected_applicant: Permill, removed_listing: Permill,
+            challenger: Permill) -> Result {
+            Self::ensure_admin(origin)?;
+
+            <RejectedApplicantSlash<T>>::put(rejected_applicant);
+            <RemovedListingSlash<T>>::put(removed_listing);
+            <ChallengerSlash<T>>::put(challenger);
+            Ok(())
+        }
+
+        fn set_jury_config(origin, jury_size: u32, #[compact] juror_penalty: BalanceOf<T>) -> Result {
+            Self::ensure_admin(origin)?;
+
+            <JurySize<T>>::put(jury_size);
+            <JurorPenalty<T>>::put(juror_penalty);
+            Ok(())
+        }
+
+        fn set_graded_mode(origin, enabled: bool) -> Result {
+            Self::ensure_admin(origin)?;
+
+            <GradedMode<T>>::put(enabled);
+            Ok(())
+        }
+
+        fn set_snapshot_voting(origin, enabled: bool) -> Result {
+            Self::ensure_admin(origin)?;
+
+            <SnapshotVoting<T>>::put(enabled);
+            Ok(())
+        }
+
+        /// Voting mode for challenges created from now on; open polls keep their mode.
+        fn set_voting_mode(origin, mode: VotingMode) -> Result {
+            Self::ensure_admin(origin)?;
+
+            <VotingModeOf<T>>::put(mode);
+            Ok(())
+        }
+
+        fn verify_voter(origin, who: T::AccountId, verified: bool) -> Result {
+            Self::ensure_admin(origin)?;
+
+            if verified {
+                <VerifiedVoters<T>>::insert(who, true);
+            } else {
+                <VerifiedVoters<T>>::remove(who);
+            }
+            Ok(())
+        }
+
+        fn add_admin(origin new_admin: T::AccountId) -> Result {
+            Self::ensure_admin(origin)?;
+
+            <Admins<T>>::insert(new_admin,ture);
+            runtime_io::print("New admin added!");
+            Ok(())
+        }
+
+        fn remove_admin(origin, admin_to_remove: T::AccountId) -> Result {
+            Self::ensure_admin(origin)?;
+
+            ensure!(Admins<T>>::exists(&admin_to_remove),
+                "The admin you are trying to remove does not exists");
+
+            <Admins<T>>::remove(admin_to_remove);
+            runtime_io::print("Admin removed!");
+            Ok(())
+        }
+
+    }
+}
+
+impl<T: Trait> Module<T> {
+    fn ensure_admin(origin: T::Origin) -> Result {
+        let sender = ensure_signed(origin)?;
+
+        ensure!(<Admins<T>>::exists(&sender),
+            "Access denied. Admin only.");
+        ensure!(Self::admins(sender) == true,
+            "Admin is not active");
+
+        Ok(())
+    }
+
+    /// Tier a whitelisted listing holds, or `None` if it is not whitelisted.
+    pub fn listing_tier(listing_hash: T::Hash) -> Option<u8> {
+        if !<Listings<T>>::exists(listing_hash) {
+            return None;
+        }
+        let listing = Self::listings(listing_hash);
+        if listing.whitelisted {
+            Some(listing.tier)
+        } else {
+            None
+        }
+    }
+
+    /// Checks whether `challenger` can challenge a listing now, returning the stake a challenge
+    /// has to match and when its voting would end.
+    fn challenge_terms(challenger: &T::AccountId, listing_hash: T::Hash,
+        listing: &Listing<BalanceOf<T>, T::AccountId, T::Moment>)
+        -> rstd::result::Result<(BalanceOf<T>, T::Moment), &'static str> {
+        ensure!(listing.challenge_id == 0 || Self::challenges(listing.challenge_id).resolved,
+            "Listing is already challenged.");
+        ensure!(&listing.owner != challenger, "You cannot challenge your own listings.");
+        let promotion = Self::promotion(listing_hash);
+        let listing_stake = Self::listing_stake(listing_hash, listing, promotion.as_ref());
+
+        let now = <timestamp::Module<T>>::get();
+
+        let commit_stage_len = Self::category_setting(listing.category_id, |c| c.commit_stage_len.clone())
+            .or_else(Self::commit_stage_len).ok_or("Commit stage length not set.")?;
+        let voting_exp = now.checked_add(&commit_stage_len).
+            ok_or("Overflow when setting voting expiry")?;
+
+        // Listings in a higher tier can be challenged for as long as they hold it.
+        ensure!((listing.tier > 0 && promotion.is_none()) || listing.application_expiry > now,
+            "Apply stage length has passed.");
+        let mut parties = vec![challenger.clone(), listing.owner.clone()];
+        parties.extend(Self::backers(listing_hash));
+        parties.extend(Self::challenge_funders(listing_hash));
+        ensure!(Self::jury_candidates(&parties).len() >= Self::jury_size() as usize,
+            "Not enough jurors staked.");
+
+        Ok((listing_stake, voting_exp))
+    }
+
+    /// Opens a challenge whose deposit has already been reserved, returning its id.
+    fn open_challenge(owner: T::AccountId, listing_hash: T::Hash,
+        listing: &Listing<BalanceOf<T>, T::AccountId, T::Moment>,
+        listing_stake: BalanceOf<T>, deposit: BalanceOf<T>, voting_exp: T::Moment,
+        reason: u8) -> u32 {
+        let challenge = Challenge {
+            listing_hash,
+            listing_owner: listing.owner.clone(),
+            deposit,
+            owner: owner.clone(),
+            voting_ends: voting_exp,
+            resolved: false,
+            reward_pool: <BalanceOf<T> as As<u64>>::sa(0),
+            total_tokens: <BalanceOf<T> as As<u64>>::sa(0),
+            reason,
+            round: 1,
+            previous: 0,
+            appellant_supports: false,
+            voided: false,
+        };
+
+        let mode = Self::voting_mode();
+        let poll = Poll {
+            listing_hash,
+            votes_for: Self::vote_weight(mode, listing_stake),
+            votes_against: Self::vote_weight(mode, deposit),
+            stake_for: listing_stake,
+            stake_against: deposit,
+            passed: false,
+            mode,
+        };
+
+        let poll_nonce = Self::open_round(challenge, poll);
+        Self::deposit_event(RawEvent::Challenged(owner,listing_hash,poll_nonce,deposit));
+        poll_nonce
+    }
+
+    /// Stores a round of a dispute and has it decided, returning its challenge id. Each
+    /// appeal round draws a jury twice the size of the last.
+    fn open_round(challenge: Challenge<T::Hash, BalanceOf<T>, T::AccountId, T::Moment>,
+        mut poll: Poll<T::Hash, BalanceOf<T>>) -> u32 {
+        let listing_hash = challenge.listing_hash;
+        let voting_exp = challenge.voting_ends.clone();
+        let jury_size = Self::jury_size()
+            .saturating_mul(2u32.saturating_pow(challenge.round - 1));
+
+        let poll_nonce = <PollNonce<T>>::get();
+        <Challenges<T>>::insert(poll_nonce,challenge);
+        if Self::snapshot_voting() {
+            <PollSnapshot<T>>::insert(poll_nonce, <system::Module<T>>::block_number());
+        }
+
+        <Listings<T>>::mutate(listing_hash, |listing| {
+            listing.challenge_id = poll_nonce;
+        });
+
+        <PollNonce<T>>::put(poll_nonce + 1);
+        T::Arbitrator::create_dispute(poll_nonce, voting_exp);
+
+        let jury = Self::draw_jury(poll_nonce, jury_size);
+        if !jury.is_empty() {
+            // A jury decides by headcount alone, so the parties' deposits carry no votes.
+            poll.votes_for = Zero::zero();
+            poll.votes_against = Zero::zero();
+            for juror in &jury {
+                <ActiveJuries<T>>::mutate(juror, |count| *count += 1);
+            }
+            <Juries<T>>::insert(poll_nonce, jury.clone());
+            Self::deposit_event(RawEvent::JuryDrawn(poll_nonce, jury));
+        }
+        <Polls<T>>::insert(poll_nonce,poll);
+
+        poll_nonce
+    }
+
+    fn store_evidence(challenge_id: u32, who: T::AccountId, evidence: Vec<u8>) {
+        <EvidenceCount<T>>::mutate((challenge_id, who.clone()), |count| *count += 1);
+        <Evidence<T>>::mutate(challenge_id, |all| all.push((who.clone(), evidence.clone())));
+        Self::deposit_event(RawEvent::EvidenceSubmitted(who, challenge_id, evidence));
+    }
+
+    /// Jurors staking at least the minimum, other than `parties`, with their stakes.
+    fn jury_candidates(parties: &[T::AccountId]) -> Vec<(T::AccountId, u64)> {
+        let min_stake = Self::min_juror_stake();
+        let max_stake = <BalanceOf<T> as As<u64>>::sa(u64::max_value());
+        Self::juror_pool().into_iter()
+            .filter(|juror| !parties.contains(juror))
+            .map(|juror| {
+                let stake = Self::juror_stake(&juror);
+                (juror, stake)
+            })
+            .filter(|(_, stake)| !stake.is_zero() && *stake >= min_stake)
+            .map(|(juror, stake)| {
+                // Stakes too large for a u64 count as the largest u64 rather than wrapping.
+                let stake: u64 = rstd::cmp::min(stake, max_stake).as_();
+                (juror, stake)
+            })
+            .collect()
+    }
+
+    /// Draws up to `size` distinct jurors for a challenge, each with odds proportional to
+    /// its stake among the jurors not yet drawn. Nobody with a stake in the dispute, in any of
+    /// its rounds, can be drawn.
+    fn draw_jury(challenge_id: u32, size: u32) -> Vec<T::AccountId> {
+        let challenge = Self::challenges(challenge_id);
+        let mut parties = vec![challenge.listing_owner.clone()];
+        parties.extend(Self::backers(challenge.listing_hash));
+        parties.extend(Self::challenge_funders(challenge.listing_hash));
+        let mut round = challenge;
+        loop {
+            parties.push(round.owner.clone());
+            if round.previous == 0 {
+                break;
+            }
+            round = Self::challenges(round.previous);
+        }
+
+        let mut candidates = Self::jury_candidates(&parties);
+        let seed = <system::Module<T>>::random_seed();
+
+        let mut jury = Vec::new();
+        for draw in 0..size {
+            let total = candidates.iter().fold(0u64, |total, (_, stake)| total.saturating_add(*stake));
+            if total == 0 {
+                break;
+            }
+            let random = T::Hashing::hash_of(&(seed, challenge_id, draw));
+            let mut point = random.as_ref().iter().take(8)
+                .fold(0u64, |acc, byte| (acc << 8) | u64::from(*byte)) % total;
+            let index = candidates.iter().position(|(_, stake)| if point < *stake {
+                true
+            } else {
+                point -= *stake;
+                false
+            }).unwrap_or(0);
+            jury.push(candidates.remove(index).0);
+        }
+        jury
+    }
+
+    /// Settles an appeal round against the dispute's final ruling. If the appeal failed, its
+    /// deposit is slashed at the rate of the side it argued for, and the slashed part is left
+    /// for the round's winning voters to claim, or goes to `respondent`, the winning party, if
+    /// nobody voted that way.
+    fn settle_appeal_round(challenge_id: u32, passed: bool, respondent: &T::AccountId,
+        listing_slash: Permill, challenger_slash: Permill) -> Result {
+        let mut challenge = Self::challenges(challenge_id);
+        let mut poll = Self::polls(challenge_id);
+        let winning_stake = if passed { poll.stake_for } else { poll.stake_against };
+
+        if challenge.appellant_supports == passed {
+            T::Currency::unreserve(&challenge.owner, challenge.deposit);
+            challenge.total_tokens = winning_stake - challenge.deposit;
+        } else {
+            let slash_rate = if challenge.appellant_supports { listing_slash } else { challenger_slash };
+            let slashed = slash_rate * challenge.deposit;
+            T::Currency::unreserve(&challenge.owner, challenge.deposit - slashed);
+            if winning_stake.is_zero() {
+                Self::repatriate(&challenge.owner, respondent, slashed)?;
+            } else {
+                challenge.reward_pool = slashed;
+                challenge.total_tokens = winning_stake;
+            }
+        }
+        Self::settle_jury(challenge_id, passed)?;
+
+        poll.passed = passed;
+        challenge.resolved = true;
+        <Challenges<T>>::insert(challenge_id, challenge);
+        <Polls<T>>::insert(challenge_id, poll);
+        Ok(())
+    }
+
+    /// Returns every deposit staked on a dispute in full, and marks each of its rounds
+    /// resolved with `passed` but voided, so that votes on it are returned too.
+    fn void_challenge(listing_hash: T::Hash, listing: &Listing<BalanceOf<T>, T::AccountId, T::Moment>,
+        challenge_id: u32, appeal_ids: Vec<u32>, passed: bool) -> Result {
+        Self::refund_listing(listing_hash, listing);
+
+        let challenge = Self::challenges(challenge_id);
+        let mut owner_deposit = challenge.deposit;
+        for funder in Self::challenge_funders(listing_hash).iter().filter(|funder| *funder != &challenge.owner) {
+            let funding = Self::challenge_funding((listing_hash, funder.clone()));
+            T::Currency::unreserve(funder, funding);
+            owner_deposit -= funding;
+        }
+        T::Currency::unreserve(&challenge.owner, owner_deposit);
+        Self::clear_challenge_funders(listing_hash);
+
+        for appeal_id in &appeal_ids {
+            let appeal = Self::challenges(*appeal_id);
+            T::Currency::unreserve(&appeal.owner, appeal.deposit);
+        }
+        for round_id in appeal_ids.into_iter().chain(Some(challenge_id)) {
+            Self::settle_jury(round_id, passed)?;
+            <Challenges<T>>::mutate(round_id, |round| {
+                round.resolved = true;
+                round.voided = true;
+            });
+            <Polls<T>>::mutate(round_id, |poll| poll.passed = passed);
+        }
+        Ok(())
+    }
+
+    /// Penalizes drawn jurors that did not vote, sharing the penalties among those that voted
+    /// with the outcome. Penalties are burned if no juror did.
+    fn settle_jury(challenge_id: u32, passed: bool) -> Result {
+        let jury = Self::jury(challenge_id);
+        for juror in &jury {
+            <ActiveJuries<T>>::mutate(juror, |count| *count -= 1);
+        }
+
+        let (voted, absent): (Vec<_>, Vec<_>) = jury.into_iter()
+            .partition(|juror| <Votes<T>>::exists((challenge_id, juror.clone())));
+        let coherent: Vec<_> = voted.into_iter()
+            .filter(|juror| Self::votes((challenge_id, juror.clone())).value == passed)
+            .collect();
+
+        for juror in absent {
+            let stake = Self::juror_stake(&juror);
+            let penalty = rstd::cmp::min(Self::juror_penalty(), stake);
+            if penalty.is_zero() {
+                continue;
+            }
+            if coherent.is_empty() {
+                T::Currency::slash_reserved(&juror, penalty);
+            } else {
+                let share = penalty / <BalanceOf<T> as As<u64>>::sa(coherent.len() as u64);
+                for winner in &coherent {
+                    Self::repatriate(&juror, winner, share)?;
+                }
+                T::Currency::slash_reserved(&juror,
+                    penalty - share * <BalanceOf<T> as As<u64>>::sa(coherent.len() as u64));
+            }
+            Self::set_juror_stake(&juror, stake - penalty);
+            Self::deposit_event(RawEvent::JurorPenalized(juror, challenge_id, penalty));
+        }
+        Ok(())
+    }
+
+    fn set_juror_stake(juror: &T::AccountId, stake: BalanceOf<T>) {
+        if stake.is_zero() {
+            <JurorStakes<T>>::remove(juror);
+            <JurorPool<T>>::mutate(|pool| pool.retain(|staker| staker != juror));
+        } else {
+            <JurorStakes<T>>::insert(juror, stake);
+        }
+    }
+
+    fn clear_challenge_funders(listing_hash: T::Hash) {
+        for funder in Self::challenge_funders(listing_hash) {
+            <ChallengeFunding<T>>::remove((listing_hash, funder));
+        }
+        <ChallengeFunders<T>>::remove(listing_hash);
+    }
+
+    fn refund_challenge_fund(listing_hash: T::Hash) {
+        for funder in Self::challenge_funders(listing_hash) {
+            T::Currency::unreserve(&funder, Self::challenge_funding((listing_hash, funder.clone())));
+        }
+        Self::clear_challenge_funders(listing_hash);
+        <ChallengeFunds<T>>::remove(listing_hash);
+
+        Self::deposit_event(RawEvent::ChallengeRefunded(listing_hash));
+    }
+
+    fn remove_listing(listing_id: u32, listing_hash: T::Hash) {
+        // A challenge still being funded has nothing left to challenge.
+        if Self::challenge_fund(listing_hash).is_some() {
+            Self::refund_challenge_fund(listing_hash);
+        }
+        for backer in Self::backers(listing_hash) {
+            <Backing<T>>::remove((listing_hash, backer));
+        }
+        <Backers<T>>::remove(listing_hash);
+        <BackedTotal<T>>::remove(listing_hash);
+        for tier in 1..=Self::listings(listing_hash).tier {
+            <TierDeposits<T>>::remove((listing_hash, tier));
+        }
+        <ListingParent<T>>::remove(listing_hash);
+        <PendingTransfers<T>>::remove(listing_hash);
+        <Listings<T>>::remove(listing_hash);
+        <ListingIndexHash<T>>::remove(listing_id);
+        <Ranking<T>>::mutate(|ranking| ranking.retain(|hash| hash != &listing_hash));
+    }
+
+    fn ensure_transferable(listing_hash: T::Hash,
+        listing: &Listing<BalanceOf<T>, T::AccountId, T::Moment>) -> Result {
+        ensure!(listing.challenge_id == 0 || Self::challenges(listing.challenge_id).resolved,
+            "Listing cannot be transferred while it is challenged.");
+        ensure!(Self::challenge_fund(listing_hash).is_none(),
+            "Listing cannot be transferred while a challenge to it is being funded.");
+        ensure!(Self::promotion(listing_hash).is_none(),
+            "Listing cannot be transferred while it is applying for promotion.");
+        Ok(())
+    }
+
+    /// Move a listing into the tier it applied for, keeping the promotion deposit staked.
+    fn promote(listing_hash: T::Hash, promotion: Promotion<BalanceOf<T>>) {
+        <Listings<T>>::mutate(listing_hash, |listing| {
+            listing.tier = promotion.tier;
+            listing.deposit += promotion.deposit;
+        });
+        <TierDeposits<T>>::insert((listing_hash, promotion.tier), promotion.deposit);
+        <Promotions<T>>::remove(listing_hash);
+
+        Self::deposit_event(RawEvent::Promoted(listing_hash, promotion.tier));
+    }
+
+    /// Move a listing down out of `tier`, whose deposit has gone to the challenge.
+    fn demote(listing_hash: T::Hash, tier: u8) {
+        let deposit = <TierDeposits<T>>::take((listing_hash, tier));
+        <Listings<T>>::mutate(listing_hash, |listing| {
+            listing.tier = tier - 1;
+            listing.deposit -= deposit;
+        });
+
+        Self::deposit_event(RawEvent::Demoted(listing_hash, tier - 1));
+    }
+
+    /// Deposit a challenge to `listing` puts at stake: the promotion deposit while it applies
+    /// for promotion, its current tier's deposit in a higher tier, and otherwise all of it.
+    fn listing_stake(listing_hash: T::Hash, listing: &Listing<BalanceOf<T>, T::AccountId, T::Moment>,
+        promotion: Option<&Promotion<BalanceOf<T>>>) -> BalanceOf<T> {
+        match promotion {
+            Some(promotion) => promotion.deposit,
+            None if listing.tier > 0 => Self::tier_deposit((listing_hash, listing.tier)),
+            None => listing.deposit,
+        }
+    }
+
+    fn category_exists(category_id: u32) -> bool {
+        category_id == 0 || <Categories<T>>::exists(category_id)
+    }
+
+    /// First override `setting` finds walking up from `category_id` towards the root.
+    fn category_setting<R, F>(category_id: u32, setting: F) -> Option<R>
+        where F: Fn(&Category<BalanceOf<T>, T::Moment>) -> Option<R>
+    {
+        let mut next = category_id;
+        while let Some(category) = Self::category(next) {
+            if let Some(value) = setting(&category) {
+                return Some(value);
+            }
+            next = category.parent;
+        }
+        None
+    }
+
+    /// Whether a listing that needs a whitelisted parent still has one.
+    fn parent_whitelisted(listing_hash: T::Hash,
+        listing: &Listing<BalanceOf<T>, T::AccountId, T::Moment>) -> bool {
+        if !Self::category(listing.category_id).map_or(false, |c| c.requires_parent) {
+            return true;
+        }
+
+        Self::listing_parent(listing_hash)
+            .map_or(false, |parent| <Listings<T>>::exists(parent) && Self::listings(parent).whitelisted)
+    }
+
+    /// Return a listing's deposit to its owner and every backer.
+    fn refund_listing(listing_hash: T::Hash, listing: &Listing<BalanceOf<T>, T::AccountId, T::Moment>) {
+        for backer in Self::backers(listing_hash) {
+            T::Currency::unreserve(&backer, Self::backing((listing_hash, backer.clone())));
+        }
+        T::Currency::unreserve(&listing.owner, listing.deposit - Self::backed_total(listing_hash));
+    }
+
+    /// Up to `count` listing hashes, highest net rank stake first.
+    pub fn top_listings(count: u32) -> Vec<T::Hash> {
+        Self::ranking().into_iter().take(count as usize).collect()
+    }
+
+    /// Move `listing_hash` to its place in `Ranking`, or drop it once it is unstaked or removed.
+    fn rerank(listing_hash: T::Hash) {
+        let totals = Self::rank_totals(listing_hash);
+        let staked = !totals.staked_for.is_zero() || !totals.staked_against.is_zero();
+
+        let mut ranking = Self::ranking();
+        ranking.retain(|hash| hash != &listing_hash);
+        if staked && <Listings<T>>::exists(listing_hash) {
+            let position = ranking.iter()
+                .position(|hash| Self::ranks_above(&totals, &Self::rank_totals(hash)))
+                .unwrap_or(ranking.len());
+            ranking.insert(position, listing_hash);
+            ranking.truncate(Self::max_ranked() as usize);
+        }
+        <Ranking<T>>::put(ranking);
+    }
+
+    /// Whether `a` has a strictly higher net stake than `b`. Compared without subtracting,
+    /// since net stake can be negative.
+    fn ranks_above(a: &RankTotals<BalanceOf<T>>, b: &RankTotals<BalanceOf<T>>) -> bool {
+        a.staked_for.saturating_add(b.staked_against) > b.staked_for.saturating_add(a.staked_against)
+    }
+
+    /// Voting weight `stake` carries under `mode`.
+    fn vote_weight(mode: VotingMode, stake: BalanceOf<T>) -> BalanceOf<T> {
+        match mode {
+            VotingMode::Linear => stake,
+            VotingMode::Quadratic => stake.integer_sqrt(),
+        }
+    }
+
+    fn add_vote(poll: &mut Poll<T::Hash, BalanceOf<T>>, value: bool, stake: BalanceOf<T>,
+        conviction: Conviction) {
+        let weight = conviction.votes(Self::vote_weight(poll.mode, stake));
+        match value {
+            true => {
+                poll.votes_for += weight;
+                poll.stake_for += stake;
+            },
+            false => {
+                poll.votes_against += weight;
+                poll.stake_against += stake;
+            },
+        }
+    }
+
+    /// Each juror's vote counts once, whatever it stakes; `stake` only sets the juror's
+    /// share of the rewards.
+    fn add_juror_vote(poll: &mut Poll<T::Hash, BalanceOf<T>>, value: bool, stake: BalanceOf<T>) {
+        let one = <BalanceOf<T> as As<u64>>::sa(1);
+        match value {
+            true => {
+                poll.votes_for += one;
+                poll.stake_for += stake;
+            },
+            false => {
+                poll.votes_against += one;
+                poll.stake_against += stake;
+            },
+        }
+    }
+
+    fn remove_vote(poll: &mut Poll<T::Hash, BalanceOf<T>>, value: bool, stake: BalanceOf<T>,
+        conviction: Conviction) {
+        let weight = conviction.votes(Self::vote_weight(poll.mode, stake));
+        match value {
+            true => {
+                poll.votes_for -= weight;
+                poll.stake_for -= stake;
+            },
+            false => {
+                poll.votes_against -= weight;
+                poll.stake_against -= stake;
+            },
+        }
+    }
+
+    /// Vote `delegator`'s delegated stake the same way as `delegate`, unless the delegator
+    /// already voted on the poll or cannot cover the stake.
+    fn cast_delegated_vote(challenge_id: u32, poll: &mut Poll<T::Hash, BalanceOf<T>>,
+        delegate: &T::AccountId, delegator: T::AccountId, value: bool) {
+        if <Votes<T>>::exists((challenge_id, delegator.clone())) {
+            return;
+        }
+        if poll.mode == VotingMode::Quadratic && !Self::is_verified(&delegator) {
+            return;
+        }
+        let (mut deposit, conviction) = match Self::delegation(&delegator) {
+            Some(delegation) => (delegation.amount, delegation.conviction),
+            None => return,
+        };
+        if let Some(snapshot) = Self::poll_snapshot(challenge_id) {
+            deposit = rstd::cmp::min(deposit, T::BalanceHistory::balance_at(&delegator, snapshot));
+        }
+        if deposit.is_zero() || T::Currency::reserve(&delegator, deposit).is_err() {
+            return;
+        }
+
+        Self::add_vote(poll, value, deposit, conviction);
+
+        <Votes<T>>::insert((challenge_id, delegator.clone()), Vote {
+            value,
+            deposit,
+            claimed: false,
+            conviction,
+            delegate: Some(delegate.clone()),
+        });
+        <DelegatedVotes<T>>::mutate((challenge_id, delegate.clone()), |delegators|
+            delegators.push(delegator.clone()));
+
+        Self::deposit_event(RawEvent::Voted(delegator, challenge_id, deposit, conviction));
+    }
+
+    /// Pay out or slash `voter`'s stake on a resolved challenge.
+    fn settle_vote(challenge_id: u32, challenge: &Challenge<T::Hash, BalanceOf<T>, T::AccountId, T::Moment>,
+        passed: bool, voter: T::AccountId, vote: Vote<BalanceOf<T>, T::AccountId>) -> Result {
+        if challenge.voided {
+            // The ruling never took effect, so the stake is returned without reward or lock.
+            T::Currency::unreserve(&voter, vote.deposit);
+            Self::deposit_event(RawEvent::Claimed(voter.clone(),challenge_id));
+        } else if passed == vote.value {
+            // An appeal round's only stake is its appellant's deposit.
+            let loser = if passed || challenge.previous != 0 {
+                challenge.owner.clone()
+            } else {
+                challenge.listing_owner.clone()
+            };
+            let reward = Self::reward_share(challenge.reward_pool,
+                vote.deposit, challenge.total_tokens)?;
+
+            Self::repatriate(&loser, &voter, reward)?;
+            T::Currency::unreserve(&voter, vote.deposit);
+
+            let lock_periods = vote.conviction.lock_periods();
+            if lock_periods > 0 {
+                let lock_len = Self::conviction_lock_period()
+                    .saturating_mul(T::BlockNumber::sa(lock_periods));
+                let until = <system::Module<T>>::block_number().saturating_add(lock_len);
+                T::Currency::extend_lock(CONVICTION_LOCK, &voter, vote.deposit, until,
+                    WithdrawReasons::all());
+            }
+
+            Self::deposit_event(RawEvent::Claimed(voter.clone(),challenge_id));
+        } else {
+            // Stake on the losing side of the poll is forfeited.
+            T::Currency::slash_reserved(&voter, vote.deposit);
+        }
+
+        <Votes<T>>::mutate((challenge_id,voter), |vote| vote.claimed = true);
+
+        Ok(())
+    }
+
+    /// Portion of `reward_pool` owed to `stake` out of `total_tokens` staked on the winning side.
+    fn reward_share(reward_pool: BalanceOf<T>, stake: BalanceOf<T>,
+        total_tokens: BalanceOf<T>) -> rstd::result::Result<BalanceOf<T>, &'static str> {
+        if total_tokens.is_zero() {
+            return Ok(Zero::zero());
+        }
+
+        reward_pool.checked_mul(&stake)
+            .ok_or("overflow in calculating reward")?
+            .checked_div(&total_tokens)
+            .ok_or("overflow in calculating reward")
+    }
+
+    /// Pay `value` out of `slashed`'s reserved stake to `beneficiary`, failing rather than
+    /// paying less than is owed.
+    fn repatriate(slashed: &T::AccountId, beneficiary: &T::AccountId, value: BalanceOf<T>) -> Result {
+        ensure!(T::Currency::reserved_balance(slashed) >= value, "Not enough reserved stake to pay out.");
+        let remainder = T::Currency::repatriate_reserved(slashed, beneficiary, value)?;
+        ensure!(remainder.is_zero(), "Not enough reserved stake to pay out.");
+        Ok(())
+    }
+}
+
+
+/// The default arbitrator: a challenge's own poll, decided by token-weighted vote when its
+/// commit stage ends.
+impl<T: Trait> Arbitrator<T::AccountId, T::Moment> for Module<T> {
+    fn create_dispute(_dispute_id: u32, _ends: T::Moment) {}
+
+    fn ruling(dispute_id: u32) -> rstd::result::Result<bool, &'static str> {
+        let challenge = Self::challenges(dispute_id);
+        ensure!(challenge.voting_ends < <timestamp::Module<T>>::get(),
+            "Commit stage length has not passed");
+        let poll = Self::polls(dispute_id);
+        // An appeal has to win votes to overturn the ruling it appeals; a tie upholds it.
+        if challenge.previous != 0 && poll.votes_for == poll.votes_against {
+            return Ok(!challenge.appellant_supports);
+        }
+        Ok(poll.votes_for > poll.votes_against)
+    }
+
+    /// Any ruling can be appealed, to a new poll.
+    fn appeal(_dispute_id: u32, _who: &T::AccountId) -> Result {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token;
+
+    use primitives::{Blake2Hasher,H256};
+    use runtime_io::with_externalities;
+    use runtimes_primitives::{
+        testing::{Digest,DigestItem,Header,UintAuthorityId},
+        traits::{BlackTwo256, IdentityLookup},
+        BuildStorage,
+    };
+    use support::{assert_noop,assert_ok,impl_outer_origin};
+
+    impl_outer_origin!{
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<u64>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+    impl consensus::Trait for Test {
+        type Log = DigestItem;
+        type SessionKey = UintAuthorityId;
+        type InherentOfflineReport = ();
+    }
+    impl token::Trait for Test {
+        type Event = ();
+        type TokenBalance = u64;
+        type AssetId = u32;
+        type ManagerOrigin = system::EnsureRoot<u64>;
+        type FreezeOrigin = system::EnsureRoot<u64>;
+        type VestingMoment = u64;
+        type VestingClock = system::Module<Test>;
+    }
+    impl timestamp::Trait for Test {
+        type Moment = u64;
+        type OnTimestampSet = ();
+    }
+    pub struct StakeAsset;
+    impl token::AssetIdProvider<u32> for StakeAsset {
+        fn asset_id() -> u32 {
+            0
+        }
+    }
+    impl Trait for Test {
+        type Event = ();
+        type Currency = Stake;
+        type BalanceHistory = Stake;
+        type Arbitrator = Tcr;
+    }
+    type Tcr = Module<Test>;
     type Token = token::Module<Test>;
+    type Stake = token::AssetCurrency<Test, StakeAsset>;
 
     fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
         let mut t = system::GenesisConfig::<Test>::default()
@@ -404,11 +2137,54 @@ mod tests {
             .unwrap()
             .0;
         t.extend(
-            token::GenesisConfig::<Test> {total_supply: 1000}
+            token::GenesisConfig::<Test> {
+                owner: 1,
+                name: b"Curation Token".to_vec(),
+                symbol: b"CRT".to_vec(),
+                decimals: 12,
+                total_supply: 1000,
+                vesting: vec![],
+                stake_unvested: false,
+                transaction_base_fee: 0,
+                transaction_byte_fee: 0,
+                fee_account: 0,
+            }
                 .build_storage()
                 .unwrap()
                 .0,
         );
+        t.extend(
+            GenesisConfig::<Test> {
+                owner: 1,
+                min_deposit: 100,
+                apply_stage_len: 10,
+                commit_stage_len: 10,
+                poll_nonce: 1,
+                max_rounds: 1,
+                appeal_period: 5,
+                rejected_applicant_slash: Permill::from_percent(100),
+                removed_listing_slash: Permill::from_percent(100),
+                challenger_slash: Permill::from_percent(100),
+                challenge_funding_len: 5,
+                max_evidence: 2,
+                max_delegators: 2,
+                min_backing: 10,
+                max_backers: 1,
+                min_challenge_funding: 10,
+                max_challenge_funders: 2,
+                snapshot_voting: false,
+                conviction_lock_period: 5,
+                unstake_cooldown: 10,
+                jury_size: 0,
+                min_juror_stake: 10,
+                max_jurors: 3,
+                max_ranked: 2,
+                juror_penalty: 0,
+            }
+            .build_storage()
+            .unwrap()
+            .0,
+        );
         t.into()
     }
 
@@ -435,8 +2211,10 @@ mod tests {
             assert_ok!(Tcr::init(Origin::signed(1)));
             assert_ok!(Tcr::propose(
                 Origin::signed(1),
+                0,
                 "ListingItem1".as_bytes().into(),
-                101
+                101,
+                None
             ));
         }));
     }
@@ -447,9 +2225,729 @@ mod tests {
             assert_ok!(Tcr::init(Origin::signed(1)));
             assert_ok!(Tcr::propose(
                 Origin::signed(1),
+                0,
                 "ListingItem1".as_bytes().into(),
-                101
+                101,
+                None
             ));
         })
     }
-}
\ No newline at end of file
+
+    fn set_timestamp(now: u64) {
+        timestamp::Module::<Test>::set_timestamp(now);
+    }
+
+    /// Initialises the registry, hands each `(account, amount)` pair its tokens
+    /// and has account 1 propose "ListingItem1" with a deposit of 100.
+    fn fund_and_propose(funded: &[(u64, u64)]) {
+        assert_ok!(Tcr::init(Origin::signed(1)));
+        for &(who, amount) in funded {
+            assert_ok!(Token::transfer(Origin::signed(1), 0, who, amount));
+        }
+        assert_ok!(Tcr::propose(Origin::signed(1), 0, "ListingItem1".as_bytes().into(), 100, None));
+    }
+
+    #[test]
+    fn should_reserve_proposal_deposit() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Tcr::init(Origin::signed(1)));
+            assert_ok!(Tcr::propose(
+                Origin::signed(1),
+                0,
+                "ListingItem1".as_bytes().into(),
+                101,
+                None
+            ));
+            assert_eq!(Token::balance_of((0, 1)), 899);
+            assert_eq!(Token::reserved_balance_of((0, 1)), 101);
+        });
+    }
+
+    #[test]
+    fn should_whitelist_unchallenged_listing() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Tcr::init(Origin::signed(1)));
+            assert_ok!(Tcr::propose(
+                Origin::signed(1),
+                0,
+                "ListingItem1".as_bytes().into(),
+                101,
+                None
+            ));
+            assert_noop!(Tcr::resolve(Origin::signed(1), 0),
+                "Apply stage length has not passed");
+
+            set_timestamp(11);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert!(Tcr::listings(Tcr::index_hash(0)).whitelisted);
+        });
+    }
+
+    #[test]
+    fn should_pay_out_successful_challenge() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(2, 300), (3, 300)]);
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 100, 0, vec![]));
+            assert_ok!(Tcr::vote(Origin::signed(3), 1, false, 100, Conviction::Locked1x));
+
+            set_timestamp(11);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert!(!<Listings<Test>>::exists(Tcr::challenges(1).listing_hash));
+            // The challenger holds half of the winning stake and takes half the pool.
+            assert_eq!(Token::balance_of((0, 2)), 350);
+            assert_eq!(Token::reserved_balance_of((0, 1)), 50);
+
+            assert_ok!(Tcr::claim_reward(Origin::signed(3), 1));
+            assert_eq!(Token::balance_of((0, 3)), 350);
+            assert_eq!(Token::reserved_balance_of((0, 1)), 0);
+            assert_noop!(Tcr::claim_reward(Origin::signed(3), 1),
+                "Vote reward has already been claimed.");
+        });
+    }
+
+    #[test]
+    fn should_cap_votes_at_snapshot_balance() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(2, 300), (3, 300)]);
+            assert_ok!(Tcr::set_snapshot_voting(Origin::signed(1), true));
+
+            system::Module::<Test>::set_block_number(2);
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 100, 0, vec![]));
+            assert_ok!(Token::transfer(Origin::signed(3), 0, 4, 100));
+
+            assert_noop!(Tcr::vote(Origin::signed(4), 1, false, 100, Conviction::Locked1x),
+                "Vote exceeds balance held when the challenge was created.");
+            assert_noop!(Tcr::vote(Origin::signed(3), 1, false, 201, Conviction::Locked1x),
+                "Not enough balance.");
+            assert_ok!(Tcr::vote(Origin::signed(3), 1, false, 200, Conviction::Locked1x));
+            assert_eq!(Token::balance_at(0, &3, 2), 300);
+        });
+    }
+
+    #[test]
+    fn should_vote_and_settle_delegated_stake() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(2, 150), (3, 150), (4, 150), (5, 150)]);
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 100, 0, vec![]));
+            assert_noop!(Tcr::delegate(Origin::signed(3), 3, 100, Conviction::Locked1x), "You cannot delegate to yourself.");
+            assert_ok!(Tcr::delegate(Origin::signed(4), 3, 100, Conviction::Locked1x));
+            assert_ok!(Tcr::delegate(Origin::signed(5), 3, 100, Conviction::Locked1x));
+            assert_noop!(Tcr::delegate(Origin::signed(2), 3, 100, Conviction::Locked1x),
+                "Delegate has too many delegators.");
+            assert_ok!(Tcr::delegate(Origin::signed(5), 3, 100, Conviction::Locked1x));
+
+            assert_ok!(Tcr::vote(Origin::signed(3), 1, false, 100, Conviction::Locked1x));
+            assert_eq!(Tcr::polls(1).votes_against, 400);
+            assert_eq!(Token::reserved_balance_of((0, 4)), 100);
+
+            // Voting directly overrides the delegate.
+            assert_ok!(Tcr::vote(Origin::signed(5), 1, true, 50, Conviction::Locked1x));
+            assert_noop!(Tcr::vote(Origin::signed(5), 1, true, 50, Conviction::Locked1x),
+                "You have already voted on this challenge.");
+            assert_eq!(Token::reserved_balance_of((0, 5)), 50);
+            assert_eq!(Tcr::polls(1).votes_against, 300);
+            assert_eq!(Tcr::polls(1).votes_for, 150);
+
+            set_timestamp(11);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert_ok!(Tcr::claim_reward(Origin::signed(3), 1));
+            assert_eq!(Token::balance_of((0, 3)), 183);
+            assert_eq!(Token::balance_of((0, 4)), 183);
+            assert_noop!(Tcr::claim_reward(Origin::signed(4), 1),
+                "Vote reward has already been claimed.");
+
+            assert_ok!(Tcr::claim_reward(Origin::signed(5), 1));
+            assert_eq!(Token::balance_of((0, 5)), 100);
+            assert_eq!(Token::reserved_balance_of((0, 5)), 0);
+        });
+    }
+
+    #[test]
+    fn should_weigh_quadratic_votes_by_square_root() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(2, 150), (3, 150), (4, 150), (5, 150)]);
+            assert_ok!(Tcr::set_voting_mode(Origin::signed(1), VotingMode::Quadratic));
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 100, 0, vec![]));
+            assert_eq!(Tcr::polls(1).votes_for, 10);
+
+            assert_noop!(Tcr::vote(Origin::signed(3), 1, true, 144, Conviction::Locked1x),
+                "Only verified voters can vote in quadratic polls.");
+            for who in 3..6 {
+                assert_ok!(Tcr::verify_voter(Origin::signed(1), who, true));
+            }
+            // One large vote is outweighed by the same stake split across two voters.
+            assert_ok!(Tcr::vote(Origin::signed(3), 1, true, 144, Conviction::Locked1x));
+            assert_ok!(Tcr::vote(Origin::signed(4), 1, false, 72, Conviction::Locked1x));
+            assert_ok!(Tcr::vote(Origin::signed(5), 1, false, 72, Conviction::Locked1x));
+            assert_eq!(Tcr::polls(1).votes_for, 22);
+            assert_eq!(Tcr::polls(1).votes_against, 26);
+            assert_eq!(Tcr::polls(1).stake_against, 244);
+
+            set_timestamp(11);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert!(!Tcr::polls(1).passed);
+            // Rewards follow tokens staked: 100 * 100 / 244 of the listing deposit to the
+            // challenger, and half of the remaining 60 to each voter.
+            assert_eq!(Token::balance_of((0, 2)), 190);
+            assert_ok!(Tcr::claim_reward(Origin::signed(4), 1));
+            assert_eq!(Token::balance_of((0, 4)), 180);
+        });
+    }
+
+    #[test]
+    fn should_weigh_backing_during_a_challenge_like_a_vote() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(2, 300), (3, 200)]);
+            assert_ok!(Tcr::set_voting_mode(Origin::signed(1), VotingMode::Quadratic));
+            assert_ok!(Tcr::set_snapshot_voting(Origin::signed(1), true));
+
+            system::Module::<Test>::set_block_number(2);
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 100, 0, vec![]));
+            assert_noop!(Tcr::back_listing(Origin::signed(3), 0, 16),
+                "Only verified voters can back listings in quadratic polls.");
+            assert_ok!(Tcr::verify_voter(Origin::signed(1), 3, true));
+
+            // Four backings of 16 weigh as much as one of 64.
+            for _ in 0..4 {
+                assert_ok!(Tcr::back_listing(Origin::signed(3), 0, 16));
+            }
+            assert_eq!(Tcr::polls(1).votes_for, 18);
+            assert_eq!(Tcr::polls(1).stake_for, 164);
+
+            assert_ok!(Token::transfer(Origin::signed(1), 0, 3, 100));
+            assert_noop!(Tcr::back_listing(Origin::signed(3), 0, 150),
+                "Backing exceeds balance held when the challenge was created.");
+        });
+    }
+
+    #[test]
+    fn should_lock_winning_conviction_votes() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(2, 300), (3, 300), (4, 50)]);
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 200, 0, vec![]));
+            // Without conviction a vote is at stake but carries no weight.
+            assert_ok!(Tcr::vote(Origin::signed(4), 1, false, 50, Conviction::None));
+            assert_eq!(Tcr::polls(1).votes_against, 200);
+            assert_eq!(Tcr::polls(1).stake_against, 250);
+            assert_ok!(Tcr::vote(Origin::signed(3), 1, true, 50, Conviction::Locked3x));
+            assert_eq!(Tcr::polls(1).votes_for, 250);
+            assert_eq!(Tcr::polls(1).stake_for, 150);
+
+            set_timestamp(11);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert!(Tcr::polls(1).passed);
+
+            system::Module::<Test>::set_block_number(1);
+            assert_ok!(Tcr::claim_reward(Origin::signed(3), 1));
+            // 300 returned stake plus a 67 reward, of which the 50 staked stays locked.
+            assert_eq!(Token::balance_of((0, 3)), 367);
+            assert_noop!(Token::transfer(Origin::signed(3), 0, 4, 318),
+                "account liquidity restrictions prevent withdrawal");
+            assert_ok!(Token::transfer(Origin::signed(3), 0, 4, 317));
+
+            // Four periods of five blocks.
+            system::Module::<Test>::set_block_number(21);
+            assert_ok!(Token::transfer(Origin::signed(3), 0, 4, 50));
+        });
+    }
+
+    #[test]
+    fn should_rank_listings_by_net_stake() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Tcr::init(Origin::signed(1)));
+            assert_ok!(Token::transfer(Origin::signed(1), 0, 2, 300));
+            for data in &["ListingItem1", "ListingItem2", "ListingItem3"] {
+                assert_ok!(Tcr::propose(Origin::signed(1), 0, data.as_bytes().into(), 100, None));
+            }
+            set_timestamp(11);
+            assert_noop!(Tcr::stake_rank(Origin::signed(2), 0, true, 10), "Registry is not graded.");
+            assert_ok!(Tcr::set_graded_mode(Origin::signed(1), true));
+            assert_noop!(Tcr::stake_rank(Origin::signed(2), 0, true, 10),
+                "Only whitelisted listings can be ranked.");
+            for id in 0..3 {
+                assert_ok!(Tcr::resolve(Origin::signed(1), id));
+            }
+            let hash = |id| Tcr::index_hash(id);
+
+            assert_ok!(Tcr::stake_rank(Origin::signed(2), 0, true, 20));
+            assert_ok!(Tcr::stake_rank(Origin::signed(2), 1, true, 50));
+            assert_ok!(Tcr::stake_rank(Origin::signed(2), 2, false, 10));
+            assert_ok!(Tcr::stake_rank(Origin::signed(1), 0, true, 40));
+            assert_noop!(Tcr::stake_rank(Origin::signed(2), 2, true, 10),
+                "Unstake before staking on the other side of a listing.");
+            assert_eq!(Tcr::top_listings(1), vec![hash(0)]);
+            // Only the top two are kept.
+            assert_eq!(Tcr::ranking(), vec![hash(0), hash(1)]);
+
+            system::Module::<Test>::set_block_number(1);
+            assert_ok!(Tcr::unstake_rank(Origin::signed(1), hash(0)));
+            assert_eq!(Tcr::ranking(), vec![hash(1), hash(0)]);
+            assert_eq!(Token::reserved_balance_of((0, 1)), 300 + 40);
+
+            assert_noop!(Tcr::withdraw_unstaked(Origin::signed(1)),
+                "No unstaked funds are ready to withdraw.");
+            system::Module::<Test>::set_block_number(11);
+            assert_ok!(Tcr::withdraw_unstaked(Origin::signed(1)));
+            assert_eq!(Token::reserved_balance_of((0, 1)), 300);
+        });
+    }
+
+    #[test]
+    fn should_apply_category_overrides() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Tcr::init(Origin::signed(1)));
+            assert_ok!(Tcr::add_category(Origin::signed(1), 0, b"Exchanges".to_vec(),
+                Some(200), None, None, false));
+            assert_ok!(Tcr::add_category(Origin::signed(1), 1, b"Exchange APIs".to_vec(),
+                None, Some(20), None, true));
+            assert_noop!(Tcr::add_category(Origin::signed(1), 5, b"Oracles".to_vec(),
+                None, None, None, false), "Parent category not found.");
+
+            assert_noop!(Tcr::propose(Origin::signed(1), 1, "Exchange1".as_bytes().into(), 150, None),
+                "deposit should be more than min_deposit");
+            assert_ok!(Tcr::propose(Origin::signed(1), 1, "Exchange1".as_bytes().into(), 200, None));
+            let exchange = Tcr::index_hash(0);
+
+            // The child category inherits its parent's minimum deposit.
+            assert_noop!(Tcr::propose(Origin::signed(1), 2, "Api1".as_bytes().into(), 200, None),
+                "This category requires a parent listing.");
+            assert_ok!(Tcr::propose(Origin::signed(1), 2, "Api1".as_bytes().into(), 200, Some(exchange)));
+            assert_ok!(Tcr::propose(Origin::signed(1), 2, "Api2".as_bytes().into(), 200, Some(exchange)));
+            assert_eq!(Token::reserved_balance_of((0, 1)), 600);
+            assert_ok!(Token::transfer(Origin::signed(1), 0, 2, 100));
+            assert_ok!(Tcr::fund_challenge(Origin::signed(2), 2, 50));
+
+            set_timestamp(11);
+            assert_noop!(Tcr::resolve(Origin::signed(1), 1), "Apply stage length has not passed");
+            set_timestamp(21);
+            // A child resolved before its parent is listed is rejected and refunded.
+            let api2 = Tcr::index_hash(2);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 2));
+            assert!(!<Listings<Test>>::exists(api2));
+            assert_eq!(Token::reserved_balance_of((0, 1)), 400);
+            // The challenge being funded against it is refunded with it.
+            assert_eq!(Tcr::challenge_fund(api2), None);
+            assert_eq!(Token::reserved_balance_of((0, 2)), 0);
+            assert_eq!(Token::balance_of((0, 2)), 100);
+
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert_ok!(Tcr::resolve(Origin::signed(1), 1));
+            assert!(Tcr::listings(Tcr::index_hash(1)).whitelisted);
+        });
+    }
+
+    #[test]
+    fn should_refund_a_challenge_to_a_listing_whose_parent_left() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Tcr::init(Origin::signed(1)));
+            for who in 2..5 {
+                assert_ok!(Token::transfer(Origin::signed(1), 0, who, 200));
+            }
+            assert_ok!(Tcr::add_category(Origin::signed(1), 0, b"Exchanges".to_vec(),
+                None, None, None, false));
+            assert_ok!(Tcr::add_category(Origin::signed(1), 1, b"Exchange APIs".to_vec(),
+                None, None, None, true));
+            assert_ok!(Tcr::propose(Origin::signed(1), 1, "Exchange1".as_bytes().into(), 100, None));
+            let exchange = Tcr::index_hash(0);
+            assert_ok!(Tcr::propose(Origin::signed(1), 2, "Api1".as_bytes().into(), 100, Some(exchange)));
+            let api = Tcr::index_hash(1);
+
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 100, 0, vec![]));
+            assert_ok!(Tcr::challenge(Origin::signed(3), 1, 100, 0, vec![]));
+            assert_ok!(Tcr::vote(Origin::signed(4), 2, true, 50, Conviction::Locked1x));
+
+            set_timestamp(11);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert!(!<Listings<Test>>::exists(exchange));
+
+            // The ruling to keep the child stands, but it leaves with its parent and every
+            // deposit on its challenge is returned.
+            assert_ok!(Tcr::resolve(Origin::signed(1), 1));
+            assert!(Tcr::polls(2).passed);
+            assert!(!<Listings<Test>>::exists(api));
+            assert_eq!(Token::reserved_balance_of((0, 3)), 0);
+            assert_eq!(Token::balance_of((0, 3)), 200);
+            assert_ok!(Tcr::claim_reward(Origin::signed(4), 2));
+            assert_eq!(Token::reserved_balance_of((0, 4)), 0);
+            assert_eq!(Token::balance_of((0, 4)), 200);
+            assert_eq!(Token::reserved_balance_of((0, 1)), 0);
+        });
+    }
+
+    #[test]
+    fn should_promote_and_demote_between_tiers() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Tcr::init(Origin::signed(1)));
+            assert_ok!(Token::transfer(Origin::signed(1), 0, 2, 300));
+            assert_ok!(Tcr::set_tier(Origin::signed(1), 1, b"Verified".to_vec(), 50, 20));
+            assert_ok!(Tcr::set_tier(Origin::signed(1), 2, b"Gold".to_vec(), 100, 20));
+            assert_noop!(Tcr::set_tier(Origin::signed(1), 4, b"Platinum".to_vec(), 100, 20),
+                "Tiers must be added in order.");
+
+            assert_ok!(Tcr::propose(Origin::signed(1), 0, "ListingItem1".as_bytes().into(), 100, None));
+            let listing_hash = Tcr::index_hash(0);
+            assert_noop!(Tcr::request_promotion(Origin::signed(1), 0, 50),
+                "Only whitelisted listings can be promoted.");
+            set_timestamp(11);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert_eq!(Tcr::listing_tier(listing_hash), Some(0));
+
+            assert_ok!(Tcr::request_promotion(Origin::signed(1), 0, 50));
+            set_timestamp(32);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert_eq!(Tcr::listing_tier(listing_hash), Some(1));
+            assert_eq!(Token::reserved_balance_of((0, 1)), 150);
+
+            // A successful challenge to the next promotion only costs the promotion deposit.
+            assert_ok!(Tcr::request_promotion(Origin::signed(1), 0, 100));
+            assert_noop!(Tcr::challenge(Origin::signed(2), 0, 99, 0, vec![]), "Not enough deposit to challenge.");
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 100, 0, vec![]));
+            assert_ok!(Tcr::vote(Origin::signed(2), 1, false, 50, Conviction::Locked1x));
+            set_timestamp(53);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert_eq!(Tcr::listing_tier(listing_hash), Some(1));
+            assert_eq!(Token::reserved_balance_of((0, 1)), 150 + 100 - 66);
+
+            // A listing in a higher tier stays open to challenge, which only risks that tier's deposit.
+            assert_noop!(Tcr::challenge(Origin::signed(2), 0, 49, 0, vec![]), "Not enough deposit to challenge.");
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 50, 0, vec![]));
+            set_timestamp(64);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert_eq!(Tcr::listing_tier(listing_hash), Some(0));
+            assert_eq!(Tcr::listings(listing_hash).deposit, 100);
+            assert_eq!(Tcr::listings(listing_hash).challenge_id, 2);
+            assert_eq!(Token::reserved_balance_of((0, 1)), 150 + 100 - 66 - 50);
+            assert_noop!(Tcr::challenge(Origin::signed(2), 0, 100, 0, vec![]), "Apply stage length has passed.");
+        });
+    }
+
+    #[test]
+    fn should_transfer_listing_after_acceptance() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(3, 300)]);
+            let listing_hash = Tcr::index_hash(0);
+
+            assert_noop!(Tcr::transfer_listing(Origin::signed(2), 0, 2),
+                "Only the listing owner can transfer it.");
+            assert_ok!(Tcr::transfer_listing(Origin::signed(1), 0, 2));
+            assert_noop!(Tcr::accept_listing(Origin::signed(3), 0), "Listing has not been offered to you.");
+
+            assert_ok!(Tcr::fund_challenge(Origin::signed(3), 0, 50));
+            assert_noop!(Tcr::accept_listing(Origin::signed(2), 0),
+                "Listing cannot be transferred while a challenge to it is being funded.");
+            assert_ok!(Tcr::fund_challenge(Origin::signed(3), 0, 50));
+            assert_ok!(Tcr::vote(Origin::signed(1), 1, true, 50, Conviction::Locked1x));
+            assert_noop!(Tcr::accept_listing(Origin::signed(2), 0),
+                "Listing cannot be transferred while it is challenged.");
+
+            set_timestamp(11);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert_ok!(Token::freeze_account(Origin::ROOT, 0, 1));
+            assert_noop!(Tcr::accept_listing(Origin::signed(2), 0), "Account is frozen.");
+            assert_ok!(Token::thaw_account(Origin::ROOT, 0, 1));
+            assert_ok!(Tcr::accept_listing(Origin::signed(2), 0));
+            assert_eq!(Tcr::listings(listing_hash).owner, 2);
+            assert_eq!(Tcr::pending_transfer(listing_hash), None);
+            // Only the listing deposit moves; the vote stays with the previous owner.
+            assert_eq!(Token::reserved_balance_of((0, 1)), 50);
+            assert_eq!(Token::reserved_balance_of((0, 2)), 100);
+            assert_eq!(Token::balance_of((0, 2)), 0);
+        });
+    }
+
+    #[test]
+    fn should_split_listing_rewards_with_backers() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(2, 200), (3, 200), (4, 200)]);
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 100, 0, vec![]));
+            assert_noop!(Tcr::back_listing(Origin::signed(1), 0, 50), "You cannot back your own listing.");
+            assert_noop!(Tcr::back_listing(Origin::signed(3), 0, 9), "Backing is below the minimum.");
+            assert_ok!(Tcr::back_listing(Origin::signed(3), 0, 100));
+            assert_noop!(Tcr::back_listing(Origin::signed(4), 0, 100), "Listing has too many backers.");
+            assert_eq!(Tcr::polls(1).stake_for, 200);
+            set_timestamp(10);
+            assert_noop!(Tcr::back_listing(Origin::signed(3), 0, 50),
+                "Listing cannot be backed once voting on its challenge has closed.");
+
+            set_timestamp(11);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            // The whole challenge deposit is the reward, split evenly between owner and backer.
+            assert_eq!(Token::balance_of((0, 1)), 300 + 50);
+            assert_eq!(Token::balance_of((0, 3)), 100 + 50);
+
+            assert_noop!(Tcr::exit(Origin::signed(3), 0), "Only the listing owner can exit.");
+            assert_ok!(Tcr::exit(Origin::signed(1), 0));
+            assert_eq!(Token::reserved_balance_of((0, 1)), 0);
+            assert_eq!(Token::reserved_balance_of((0, 3)), 0);
+            assert_eq!(Token::balance_of((0, 1)), 450);
+            assert_eq!(Token::balance_of((0, 3)), 250);
+            assert!(Tcr::backers(Tcr::challenges(1).listing_hash).is_empty());
+        });
+    }
+
+    #[test]
+    fn should_forfeit_backing_of_rejected_listing() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(2, 200), (3, 200), (4, 200)]);
+            assert_ok!(Tcr::back_listing(Origin::signed(3), 0, 100));
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 200, 0, vec![]));
+            assert_ok!(Tcr::vote(Origin::signed(4), 1, false, 100, Conviction::None));
+
+            set_timestamp(11);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert!(!Tcr::polls(1).passed);
+            assert_eq!(Token::reserved_balance_of((0, 3)), 0);
+            assert_eq!(Token::balance_of((0, 3)), 100);
+            // 200 * 200 / 300 of the listing deposit to the challenger, the rest left for voters.
+            assert_eq!(Token::balance_of((0, 2)), 200 + 133);
+            assert_eq!(Token::reserved_balance_of((0, 1)), 67);
+        });
+    }
+
+    #[test]
+    fn should_split_crowdfunded_challenge_rewards() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(2, 200), (3, 200), (4, 200)]);
+
+            assert_ok!(Tcr::fund_challenge(Origin::signed(2), 0, 50));
+            assert_noop!(Tcr::fund_challenge(Origin::signed(3), 0, 9), "Contribution is below the minimum.");
+            assert_ok!(Tcr::fund_challenge(Origin::signed(3), 0, 10));
+            assert_noop!(Tcr::fund_challenge(Origin::signed(4), 0, 10), "Challenge has too many funders.");
+            assert_noop!(Tcr::challenge(Origin::signed(4), 0, 100, 0, vec![]),
+                "A challenge to this listing is already being funded.");
+            set_timestamp(3);
+            assert_noop!(Tcr::refund_challenge(Origin::signed(1), 0),
+                "Challenge funding period has not passed.");
+            set_timestamp(6);
+            assert_noop!(Tcr::fund_challenge(Origin::signed(3), 0, 50),
+                "Challenge funding period has passed.");
+            assert_ok!(Tcr::refund_challenge(Origin::signed(1), 0));
+            assert_eq!(Token::balance_of((0, 2)), 200);
+            assert_eq!(Token::balance_of((0, 3)), 200);
+
+            assert_ok!(Tcr::fund_challenge(Origin::signed(2), 0, 60));
+            assert_ok!(Tcr::fund_challenge(Origin::signed(3), 0, 40));
+            assert_eq!(Tcr::challenges(1).owner, 2);
+            assert_eq!(Tcr::polls(1).stake_against, 100);
+            assert_ok!(Tcr::vote(Origin::signed(4), 1, false, 100, Conviction::Locked1x));
+
+            set_timestamp(17);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            // Half the listing deposit goes to the challengers, split 60:40.
+            assert_eq!(Token::balance_of((0, 2)), 200 + 30);
+            assert_eq!(Token::balance_of((0, 3)), 200 + 20);
+            assert_eq!(Token::reserved_balance_of((0, 2)), 0);
+            assert_eq!(Token::reserved_balance_of((0, 3)), 0);
+            assert_eq!(Token::reserved_balance_of((0, 1)), 50);
+        });
+    }
+
+    #[test]
+    fn should_collect_evidence_from_parties_and_voters() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(2, 200), (3, 200), (4, 200)]);
+            assert_noop!(Tcr::challenge(Origin::signed(2), 0, 100, 3, vec![0; 257]),
+                "Evidence cannot be more than 256 bytes");
+            <MaxEvidence<Test>>::put(0);
+            assert_noop!(Tcr::challenge(Origin::signed(2), 0, 100, 3, b"ipfs://spam-report".to_vec()),
+                "You cannot submit more evidence on this challenge.");
+            <MaxEvidence<Test>>::put(2);
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 100, 3, b"ipfs://spam-report".to_vec()));
+            assert_eq!(Tcr::challenges(1).reason, 3);
+
+            assert_noop!(Tcr::submit_evidence(Origin::signed(3), 1, b"ipfs://rebuttal".to_vec()),
+                "Only parties to the challenge and its voters can submit evidence.");
+            assert_ok!(Tcr::vote(Origin::signed(3), 1, true, 50, Conviction::Locked1x));
+            assert_ok!(Tcr::submit_evidence(Origin::signed(3), 1, b"ipfs://rebuttal".to_vec()));
+            assert_ok!(Tcr::submit_evidence(Origin::signed(1), 1, b"ipfs://audit".to_vec()));
+            assert_ok!(Tcr::submit_evidence(Origin::signed(2), 1, b"ipfs://logs".to_vec()));
+            assert_noop!(Tcr::submit_evidence(Origin::signed(2), 1, b"ipfs://more".to_vec()),
+                "You cannot submit more evidence on this challenge.");
+
+            assert_eq!(Tcr::evidence(1), vec![
+                (2, b"ipfs://spam-report".to_vec()),
+                (3, b"ipfs://rebuttal".to_vec()),
+                (1, b"ipfs://audit".to_vec()),
+                (2, b"ipfs://logs".to_vec()),
+            ]);
+        });
+    }
+
+    #[test]
+    fn should_resolve_by_poll_arbitrator_ruling() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(2, 200)]);
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 100, 0, vec![]));
+
+            assert_noop!(Tcr::resolve(Origin::signed(1), 0), "Commit stage length has not passed");
+            assert_ok!(Tcr::vote(Origin::signed(1), 1, true, 10, Conviction::Locked1x));
+
+            set_timestamp(11);
+            assert_eq!(<Tcr as Arbitrator<u64, u64>>::ruling(1), Ok(true));
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert!(Tcr::polls(1).passed);
+        });
+    }
+
+    #[test]
+    fn should_draw_stake_weighted_jury_and_penalize_absent_jurors() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Tcr::init(Origin::signed(1)));
+            for who in 2..6 {
+                assert_ok!(Token::transfer(Origin::signed(1), 0, who, 200));
+            }
+            assert_noop!(Tcr::stake_juror(Origin::signed(3), 9), "Juror stake is below the minimum.");
+            assert_ok!(Tcr::stake_juror(Origin::signed(2), 50));
+            assert_ok!(Tcr::stake_juror(Origin::signed(3), 50));
+            assert_ok!(Tcr::stake_juror(Origin::signed(4), 50));
+            assert_noop!(Tcr::stake_juror(Origin::signed(5), 50), "Juror pool is full.");
+            // The challenger is staked as a juror too, but cannot sit on its own dispute.
+            assert_ok!(Tcr::set_jury_config(Origin::signed(1), 3, 10));
+            assert_ok!(Tcr::propose(Origin::signed(1), 0, "ListingItem1".as_bytes().into(), 100, None));
+            assert_noop!(Tcr::challenge(Origin::signed(2), 0, 100, 0, vec![]), "Not enough jurors staked.");
+
+            assert_ok!(Tcr::set_jury_config(Origin::signed(1), 2, 10));
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 100, 0, vec![]));
+            let mut jury = Tcr::jury(1);
+            jury.sort();
+            assert_eq!(jury, vec![3, 4]);
+
+            assert_noop!(Tcr::vote(Origin::signed(5), 1, false, 10, Conviction::Locked1x),
+                "Only drawn jurors can vote on this challenge.");
+            assert_ok!(Tcr::vote(Origin::signed(3), 1, true, 10, Conviction::Locked1x));
+            assert_noop!(Tcr::unstake_juror(Origin::signed(3), 50),
+                "Jurors cannot unstake while drawn for an unresolved challenge.");
+
+            set_timestamp(11);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert!(Tcr::polls(1).passed);
+            // Juror 4 did not vote, and its penalty goes to juror 3.
+            assert_eq!(Tcr::juror_stake(4), 40);
+            assert_eq!(Token::reserved_balance_of((0, 4)), 40);
+            assert_eq!(Token::balance_of((0, 3)), 150);
+
+            assert_noop!(Tcr::unstake_juror(Origin::signed(4), 35),
+                "Juror stake cannot fall below the minimum.");
+            assert_ok!(Tcr::unstake_juror(Origin::signed(3), 50));
+            assert_eq!(Tcr::juror_pool(), vec![2, 4]);
+        });
+    }
+
+    #[test]
+    fn should_decide_jury_challenges_by_headcount() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(2, 400), (3, 400), (4, 400)]);
+            for who in 2..5 {
+                assert_ok!(Tcr::stake_juror(Origin::signed(who), 50));
+            }
+            assert_ok!(Tcr::set_jury_config(Origin::signed(1), 2, 10));
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 300, 0, vec![]));
+            assert_eq!(Tcr::polls(1).votes_for, 0);
+            assert_eq!(Tcr::polls(1).votes_against, 0);
+            assert_eq!(Tcr::polls(1).stake_against, 300);
+
+            // One juror outvotes the challenger's larger deposit, staking nothing itself.
+            assert_ok!(Tcr::vote(Origin::signed(3), 1, true, 0, Conviction::Locked1x));
+            assert_eq!(Tcr::polls(1).votes_for, 1);
+
+            set_timestamp(11);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert!(Tcr::polls(1).passed);
+            assert_eq!(Tcr::juror_stake(4), 40);
+        });
+    }
+
+    #[test]
+    fn should_apply_only_the_final_round_of_appeals() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(2, 400), (3, 200), (4, 200)]);
+            assert_ok!(Tcr::set_appeal_config(Origin::signed(1), 2, 5));
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 100, 0, vec![]));
+            assert_ok!(Tcr::vote(Origin::signed(3), 1, true, 50, Conviction::Locked1x));
+
+            set_timestamp(11);
+            assert_noop!(Tcr::resolve(Origin::signed(1), 0), "Appeal period has not passed.");
+            assert_noop!(Tcr::appeal_round(Origin::signed(2), 0, 150),
+                "Appeal deposit must be at least double the last round's.");
+            assert_ok!(Tcr::appeal_round(Origin::signed(2), 0, 200));
+            assert_eq!(Tcr::challenges(2).round, 2);
+            // The appeal deposit is at stake but casts no votes.
+            assert_eq!(Tcr::polls(2).stake_against, 200);
+            assert_eq!(Tcr::polls(2).votes_against, 0);
+            assert!(!Tcr::listings(Tcr::challenges(2).listing_hash).whitelisted);
+            assert_ok!(Tcr::vote(Origin::signed(3), 2, true, 50, Conviction::Locked1x));
+            assert_ok!(Tcr::vote(Origin::signed(4), 2, false, 100, Conviction::Locked1x));
+
+            set_timestamp(22);
+            assert_noop!(Tcr::appeal_round(Origin::signed(1), 0, 400), "No appeal rounds remain.");
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert!(!Tcr::polls(1).passed);
+            assert_noop!(Tcr::resolve(Origin::signed(1), 0), "Listing not found.");
+
+            // The challenger wins the listing deposit and gets both round deposits back.
+            assert_eq!(Token::balance_of((0, 2)), 500);
+            assert_eq!(Token::reserved_balance_of((0, 1)), 0);
+            assert_ok!(Tcr::claim_reward(Origin::signed(4), 2));
+            assert_eq!(Token::balance_of((0, 4)), 200);
+            assert_ok!(Tcr::claim_reward(Origin::signed(3), 1));
+            assert_ok!(Tcr::claim_reward(Origin::signed(3), 2));
+            assert_eq!(Token::reserved_balance_of((0, 3)), 0);
+            assert_eq!(Token::balance_of((0, 3)), 100);
+        });
+    }
+
+    #[test]
+    fn should_uphold_the_ruling_when_an_appeal_round_ties() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(2, 400), (3, 100)]);
+            assert_ok!(Tcr::set_appeal_config(Origin::signed(1), 2, 5));
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 100, 0, vec![]));
+            assert_ok!(Tcr::vote(Origin::signed(3), 1, true, 50, Conviction::Locked1x));
+
+            set_timestamp(11);
+            assert_ok!(Tcr::appeal_round(Origin::signed(2), 0, 300));
+
+            // Nobody votes in the appeal, so the listing stays despite the larger deposit.
+            set_timestamp(22);
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert!(Tcr::polls(1).passed);
+            assert!(Tcr::polls(2).passed);
+            assert!(Tcr::listings(Tcr::challenges(1).listing_hash).whitelisted);
+            assert_ok!(Tcr::claim_reward(Origin::signed(3), 1));
+            assert_eq!(Token::reserved_balance_of((0, 2)), 0);
+        });
+    }
+
+    #[test]
+    fn should_refund_unslashed_part_of_losing_deposits() {
+        with_externalities(&mut new_test_ext(), || {
+            fund_and_propose(&[(2, 200), (3, 200), (4, 200)]);
+            assert_ok!(Tcr::set_slash_rates(Origin::signed(1), Permill::from_percent(50),
+                Permill::from_percent(100), Permill::from_percent(20)));
+
+            assert_ok!(Tcr::propose(Origin::signed(1), 0, "ListingItem2".as_bytes().into(), 100, None));
+            assert_ok!(Tcr::challenge(Origin::signed(2), 0, 100, 0, vec![]));
+            assert_ok!(Tcr::challenge(Origin::signed(4), 1, 100, 0, vec![]));
+            assert_ok!(Tcr::vote(Origin::signed(3), 1, false, 100, Conviction::Locked1x));
+            assert_ok!(Tcr::vote(Origin::signed(3), 2, true, 50, Conviction::Locked1x));
+
+            set_timestamp(11);
+            // The rejected applicant loses half its deposit: 25 to the challenger, 25 to voters.
+            assert_ok!(Tcr::resolve(Origin::signed(1), 0));
+            assert_eq!(Token::balance_of((0, 1)), 250);
+            assert_eq!(Token::balance_of((0, 2)), 225);
+            // The failed challenger loses a fifth of its deposit: 13 to the owner, 7 to voters.
+            assert_ok!(Tcr::resolve(Origin::signed(1), 1));
+            assert_eq!(Token::balance_of((0, 1)), 263);
+            assert_eq!(Token::balance_of((0, 4)), 180);
+            assert_eq!(Token::reserved_balance_of((0, 4)), 7);
+
+            assert_ok!(Tcr::claim_reward(Origin::signed(3), 1));
+            assert_ok!(Tcr::claim_reward(Origin::signed(3), 2));
+            assert_eq!(Token::balance_of((0, 3)), 232);
+            assert_eq!(Token::reserved_balance_of((0, 1)), 100);
+            assert_eq!(Token::reserved_balance_of((0, 4)), 0);
+        });
+    }
+
+}
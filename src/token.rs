@@ -1,135 +1,1519 @@
-use rstd::prelude::*;
+use rstd::{prelude::*,result,cmp,marker::PhantomData};
 use parity_codec::Codec;
+use parity_codec_derive::{Decode,Encode};
 use support::{dispatch::Result,StorageMap,Parameter,StorageValue,
-    decl_storage,decl_event,ensure};
+    decl_module,decl_storage,decl_event,ensure};
+use support::traits::{Currency,ReservableCurrency,LockableCurrency,Imbalance,
+    SignedImbalance,UpdateBalanceOutcome,WithdrawReason,WithdrawReasons,
+    ExistenceRequirement,LockIdentifier};
 use system::{self,ensure_signed};
-use runtime_primitives::traits::{CheckedSub,CheckedAdd,Member,SimpleArithmetic,As};
+use runtime_primitives::traits::{CheckedSub,CheckedAdd,Member,SimpleArithmetic,As,
+    EnsureOrigin,MaybeSerializeDebug,Zero,One,MakePayment,Saturating};
+
+pub use self::imbalances::{PositiveImbalance,NegativeImbalance};
 
 pub trait Trait: system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
-    type TokenBanlace: Parameter + Member + SimpleArithmetic + Codec
-        + Default + As<usize> + As<u64>;
+    type TokenBalance: Parameter + Member + SimpleArithmetic + Codec
+        + Default + Copy + MaybeSerializeDebug + As<usize> + As<u64>;
+    /// Identifies an asset. The asset configured at genesis has the default id.
+    type AssetId: Parameter + Member + SimpleArithmetic + Codec + Default + Copy;
+    /// Origin allowed to mint new tokens and burn tokens from any account.
+    type ManagerOrigin: EnsureOrigin<Self::Origin>;
+    /// Origin allowed to freeze accounts and pause assets.
+    type FreezeOrigin: EnsureOrigin<Self::Origin>;
+    /// The unit vesting schedules are measured in, e.g. block number or timestamp.
+    type VestingMoment: Parameter + SimpleArithmetic + Default + Copy;
+    /// Source of the current `VestingMoment`.
+    type VestingClock: VestingClock<Self::VestingMoment>;
+}
+
+/// Something that tells the current time for vesting purposes.
+pub trait VestingClock<Moment> {
+    fn now() -> Moment;
+}
+
+impl<T: system::Trait> VestingClock<T::BlockNumber> for system::Module<T> {
+    fn now() -> T::BlockNumber {
+        <system::Module<T>>::block_number()
+    }
+}
+
+impl<T: timestamp::Trait> VestingClock<T::Moment> for timestamp::Module<T> {
+    fn now() -> T::Moment {
+        <timestamp::Module<T>>::get()
+    }
+}
+
+/// Picks the asset an `AssetCurrency` operates on.
+pub trait AssetIdProvider<AssetId> {
+    fn asset_id() -> AssetId;
+}
+
+/// Display information about an asset.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode,Decode,Default,Clone,PartialEq)]
+pub struct TokenMetadata {
+    pub name: Vec<u8>,
+    pub symbol: Vec<u8>,
+    pub decimals: u8,
+}
+
+/// Look up what an account's balance was in the past.
+pub trait BalanceHistory<AccountId, BlockNumber, Balance> {
+    /// Balance `who` held at the start of `block`.
+    fn balance_at(who: &AccountId, block: BlockNumber) -> Balance;
+}
+
+/// Move reserved funds between accounts without them ever becoming free. Implemented for
+/// each asset's `AssetCurrency` and for the native `balances` module.
+pub trait TransferReserved<AccountId, Balance> {
+    /// Fails if either account is frozen or the asset is paused.
+    fn transfer_reserved(from: &AccountId, to: &AccountId, value: Balance) -> Result;
+    /// Like slashing, this ignores frozen accounts and paused assets, so that a frozen party
+    /// cannot hold up settling a dispute.
+    fn slash_reserved_to(from: &AccountId, to: &AccountId, value: Balance) -> Result;
+}
+
+/// An asset's total supply alongside the sums of every balance in its ledger.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode,Decode,Default,Clone,PartialEq)]
+pub struct AuditReport<Balance> {
+    pub total_supply: Balance,
+    /// Sum of every account's free balance.
+    pub free: Balance,
+    /// Sum of every account's reserved balance.
+    pub reserved: Balance,
+    /// Sum of every deposit held through `lock`.
+    pub locked: Balance,
+}
+
+impl<Balance: SimpleArithmetic + Copy> AuditReport<Balance> {
+    /// Whether the total supply equals everything held in the ledger.
+    pub fn is_balanced(&self) -> bool {
+        self.free.checked_add(&self.reserved)
+            .and_then(|held| held.checked_add(&self.locked))
+            .map_or(false, |held| held == self.total_supply)
+    }
+}
+
+/// A cliff followed by a linear release of `locked` tokens.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode,Decode,Default,Clone,PartialEq,Eq)]
+pub struct VestingSchedule<Balance, Moment> {
+    /// Amount locked at `start`.
+    pub locked: Balance,
+    pub start: Moment,
+    /// Nothing is released before `start + cliff`.
+    pub cliff: Moment,
+    /// Everything is released at `start + duration`.
+    pub duration: Moment,
+}
+
+impl<Balance: SimpleArithmetic + Copy, Moment: SimpleArithmetic + Copy> VestingSchedule<Balance, Moment> {
+    /// Whether the cliff ends within the schedule and the schedule ends at a representable
+    /// moment.
+    pub fn is_valid(&self) -> bool {
+        self.cliff <= self.duration && self.start.checked_add(&self.duration).is_some()
+    }
+
+    /// Amount still locked at `now`.
+    pub fn locked_at(&self, now: Moment) -> Balance {
+        match self.start.checked_add(&self.cliff) {
+            Some(cliff_end) if now >= cliff_end => (),
+            _ => return self.locked,
+        }
+
+        let elapsed = now - self.start;
+        if elapsed >= self.duration {
+            return Zero::zero();
+        }
+
+        let elapsed = Balance::sa(elapsed.as_());
+        let duration = Balance::sa(self.duration.as_());
+        let released = self.locked.checked_mul(&elapsed)
+            .map(|v| v / duration)
+            .unwrap_or_else(|| self.locked / duration * elapsed);
+        self.locked - released
+    }
 }
 
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         fn deposit_event<T>() = default;
 
-        pub fn transfer(origin, to: AccountId, #[compact] value: T::TokenBalance) -> Result {
+        /// Issue a new asset with a fixed initial supply held by the caller.
+        pub fn create_asset(origin, #[compact] total_supply: T::TokenBalance,
+            name: Vec<u8>, symbol: Vec<u8>, decimals: u8) -> Result {
             let sender = ensure_signed(origin)?;
-            Self::transfer(sender, to ,value)
+
+            let asset_id = Self::next_asset_id();
+            let next_asset_id = asset_id.checked_add(&One::one())
+                .ok_or("No new assets can be created.")?;
+            Self::put_metadata(asset_id, name, symbol, decimals)?;
+
+            <NextAssetId<T>>::put(next_asset_id);
+            <TotalSupply<T>>::insert(asset_id, total_supply);
+            Self::set_free_balance(asset_id, &sender, total_supply);
+
+            Self::deposit_event(RawEvent::AssetCreated(asset_id, sender, total_supply));
+            Ok(())
+        }
+
+        pub fn transfer(origin, #[compact] asset_id: T::AssetId, to: T::AccountId,
+            #[compact] value: T::TokenBalance) -> Result {
+            let sender = ensure_signed(origin)?;
+            Self::_transfer(asset_id, sender, to ,value)
         }
 
-        pub fn approve(origin, spender: T::AccountId, #[compact] value: T::TokenBalance) -> Result {
+        pub fn approve(origin, #[compact] asset_id: T::AssetId, spender: T::AccountId,
+            #[compact] value: T::TokenBalance) -> Result {
             let sender = ensure_signed(origin)?;
-            enusre!(<BalanceOf<T>>::exists(&sender), "Account does not own this token");
-            let allowance = Self::allowance((sender.clone(),spender.clone()));
+            ensure!(<BalanceOf<T>>::exists((asset_id, sender.clone())),
+                "Account does not own this token");
+
+            Self::set_allowance(asset_id, sender, spender, value);
+            Ok(())
+        }
+
+        pub fn increase_allowance(origin, #[compact] asset_id: T::AssetId, spender: T::AccountId,
+            #[compact] value: T::TokenBalance) -> Result {
+            let sender = ensure_signed(origin)?;
+            let allowance = Self::allowance((asset_id,sender.clone(),spender.clone()));
             let updated_allowance = allowance.checked_add(&value)
                 .ok_or("overflow in calculating allowance")?;
-            <Allownance<T>>::insert((sender.clone(),spender.clone())),
 
-            Self::deposit_event(RawEvent::Approval(sender,spender,value));
+            Self::set_allowance(asset_id, sender, spender, updated_allowance);
             Ok(())
         }
 
-        pub fn transfer_from(_origin, from: T::AccountId, to: T::AccountId, #[compact] value: T::TokenBalance) -> Result {
-            ensure!(<Allownance<T>>::exists((from.clone(),to.clone())),"Allowance does not exists.");
-            let allowance = Self::allowance((from.clone(),to.clone()));
+        pub fn decrease_allowance(origin, #[compact] asset_id: T::AssetId, spender: T::AccountId,
+            #[compact] value: T::TokenBalance) -> Result {
+            let sender = ensure_signed(origin)?;
+            let allowance = Self::allowance((asset_id,sender.clone(),spender.clone()));
+            let updated_allowance = allowance.checked_sub(&value)
+                .ok_or("Allowance cannot be decreased below zero.")?;
+
+            Self::set_allowance(asset_id, sender, spender, updated_allowance);
+            Ok(())
+        }
+
+        pub fn transfer_from(origin, #[compact] asset_id: T::AssetId, from: T::AccountId,
+            to: T::AccountId, #[compact] value: T::TokenBalance) -> Result {
+            let spender = ensure_signed(origin)?;
+
+            ensure!(<Allowance<T>>::exists((asset_id,from.clone(),spender.clone())),
+                "Allowance does not exists.");
+            let allowance = Self::allowance((asset_id,from.clone(),spender.clone()));
             ensure!(allowance >= value, "Not enough allowance");
 
-            let updated_allowance = allowance.check_sub(&value).ok_or(overflow in calculating allowance)?;
+            let updated_allowance = allowance.checked_sub(&value)
+                .ok_or("overflow in calculating allowance")?;
+
+            Self::_transfer(asset_id, from.clone(), to, value)?;
+            Self::set_allowance(asset_id, from, spender, updated_allowance);
+            Ok(())
+        }
+
+        pub fn mint(origin, #[compact] asset_id: T::AssetId, to: T::AccountId,
+            #[compact] value: T::TokenBalance) -> Result {
+            T::ManagerOrigin::ensure_origin(origin)?;
+            Self::_mint(asset_id, to, value)
+        }
+
+        pub fn burn_from(origin, #[compact] asset_id: T::AssetId, from: T::AccountId,
+            #[compact] value: T::TokenBalance) -> Result {
+            T::ManagerOrigin::ensure_origin(origin)?;
+            Self::_burn(asset_id, from, value)
+        }
+
+        pub fn burn(origin, #[compact] asset_id: T::AssetId, #[compact] value: T::TokenBalance) -> Result {
+            let sender = ensure_signed(origin)?;
+            Self::_burn(asset_id, sender, value)
+        }
+
+        /// Offer `to` tokens under a vesting schedule. Nothing moves until `to` accepts, so no
+        /// account can be given a schedule it did not ask for.
+        pub fn vested_transfer(origin, #[compact] asset_id: T::AssetId, to: T::AccountId,
+            schedule: VestingSchedule<T::TokenBalance, T::VestingMoment>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(sender != to, "You cannot offer yourself a vesting schedule.");
+            ensure!(schedule.is_valid(), "Vesting cliff cannot be longer than its duration.");
+
+            let locked = schedule.locked;
+            <VestingOffers<T>>::insert((asset_id, sender.clone(), to.clone()), schedule);
+            Self::deposit_event(RawEvent::VestingOffered(asset_id, sender, to, locked));
+            Ok(())
+        }
+
+        /// Take the tokens `from` offered under a vesting schedule.
+        pub fn accept_vesting(origin, #[compact] asset_id: T::AssetId, from: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+            let schedule = Self::vesting_offer((asset_id, from.clone(), sender.clone()))
+                .ok_or("No vesting schedule has been offered to you.")?;
+            ensure!(!<Vesting<T>>::exists((asset_id, sender.clone())),
+                "Account already has a vesting schedule.");
+
+            Self::_transfer(asset_id, from.clone(), sender.clone(), schedule.locked)?;
+            <VestingOffers<T>>::remove((asset_id, from, sender.clone()));
+            <Vesting<T>>::insert((asset_id, sender), schedule);
+
+            Ok(())
+        }
+
+        pub fn set_metadata(origin, #[compact] asset_id: T::AssetId, name: Vec<u8>,
+            symbol: Vec<u8>, decimals: u8) -> Result {
+            T::ManagerOrigin::ensure_origin(origin)?;
+            ensure!(<TotalSupply<T>>::exists(asset_id), "Asset does not exist.");
+
+            Self::put_metadata(asset_id, name, symbol, decimals)
+        }
+
+        /// Change how transaction fees are charged when the runtime pays fees in this token.
+        /// Fees are burned while `fee_account` is `None`.
+        pub fn set_fees(origin, #[compact] base_fee: T::TokenBalance,
+            #[compact] byte_fee: T::TokenBalance, fee_account: Option<T::AccountId>) -> Result {
+            T::ManagerOrigin::ensure_origin(origin)?;
+
+            <TransactionBaseFee<T>>::put(base_fee);
+            <TransactionByteFee<T>>::put(byte_fee);
+            match fee_account {
+                Some(account) => <FeeAccount<T>>::put(account),
+                None => <FeeAccount<T>>::kill(),
+            }
+
+            Ok(())
+        }
+
+        /// Stop `who` from moving or staking its `asset_id` tokens.
+        pub fn freeze_account(origin, #[compact] asset_id: T::AssetId, who: T::AccountId) -> Result {
+            T::FreezeOrigin::ensure_origin(origin)?;
+            ensure!(!Self::is_frozen((asset_id, who.clone())), "Account is already frozen.");
+
+            <Frozen<T>>::insert((asset_id, who.clone()), true);
+            Self::deposit_event(RawEvent::AccountFrozen(asset_id, who));
+            Ok(())
+        }
+
+        pub fn thaw_account(origin, #[compact] asset_id: T::AssetId, who: T::AccountId) -> Result {
+            T::FreezeOrigin::ensure_origin(origin)?;
+            ensure!(Self::is_frozen((asset_id, who.clone())), "Account is not frozen.");
+
+            <Frozen<T>>::remove((asset_id, who.clone()));
+            Self::deposit_event(RawEvent::AccountThawed(asset_id, who));
+            Ok(())
+        }
+
+        /// Stop every account from moving or staking `asset_id` tokens.
+        pub fn pause(origin, #[compact] asset_id: T::AssetId) -> Result {
+            T::FreezeOrigin::ensure_origin(origin)?;
+            ensure!(!Self::is_paused(asset_id), "Asset is already paused.");
+
+            <Paused<T>>::insert(asset_id, true);
+            Self::deposit_event(RawEvent::AssetPaused(asset_id));
+            Ok(())
+        }
 
-            <Allowance<T>>::insert((from.clone(),to.clone()),updated_allowance);
-            
-            Self::deposit_event(RawEvent::Approval(from.clone(),to.clone,value));
-            Self::_transfer(from,to,value)
-        } 
+        pub fn unpause(origin, #[compact] asset_id: T::AssetId) -> Result {
+            T::FreezeOrigin::ensure_origin(origin)?;
+            ensure!(Self::is_paused(asset_id), "Asset is not paused.");
+
+            <Paused<T>>::remove(asset_id);
+            Self::deposit_event(RawEvent::AssetUnpaused(asset_id));
+            Ok(())
+        }
+
+        /// Drop the caller's vesting schedule once everything in it has been released.
+        pub fn vest(origin, #[compact] asset_id: T::AssetId) -> Result {
+            let sender = ensure_signed(origin)?;
+            let schedule = Self::vesting((asset_id, sender.clone()))
+                .ok_or("Account has no vesting schedule.")?;
+
+            let locked = schedule.locked_at(T::VestingClock::now());
+            if locked.is_zero() {
+                <Vesting<T>>::remove((asset_id, sender.clone()));
+            }
+
+            Self::deposit_event(RawEvent::Vested(asset_id, sender, locked));
+            Ok(())
+        }
     }
 }
 
 decl_storage! {
     trait Store for Module<T: Trait> as Token {
-        Init get(is_init): bool;
-        TotalSupply get(total_supply) config(): T::TokenBalance;
-        BalanceOf get(balance_of): map T::AccountId => T::TokenBalance;
-        Allowance get(allowance): map (T::AccountId, T::AccountId) => T::TokenBalance;
-        LockedDeposits get(locked_deposits): map T::Hash => T::TokenBalance;
+        /// Account the genesis asset's supply is credited to at genesis.
+        Owner get(owner) config(): T::AccountId;
+        NextAssetId get(next_asset_id) build(|_: &GenesisConfig<T>| T::AssetId::one()): T::AssetId;
+        Name get(name) build(|config: &GenesisConfig<T>| {
+            vec![(T::AssetId::default(), config.name.clone())]
+        }): map T::AssetId => Vec<u8>;
+        Symbol get(symbol) build(|config: &GenesisConfig<T>| {
+            vec![(T::AssetId::default(), config.symbol.clone())]
+        }): map T::AssetId => Vec<u8>;
+        /// Number of decimal places an asset's `TokenBalance` should be displayed with.
+        Decimals get(decimals) build(|config: &GenesisConfig<T>| {
+            vec![(T::AssetId::default(), config.decimals)]
+        }): map T::AssetId => u8;
+        TotalSupply get(total_supply) build(|config: &GenesisConfig<T>| {
+            vec![(T::AssetId::default(), config.total_supply)]
+        }): map T::AssetId => T::TokenBalance;
+        BalanceOf get(balance_of) build(|config: &GenesisConfig<T>| {
+            vec![((T::AssetId::default(), config.owner.clone()), config.total_supply)]
+        }): map (T::AssetId, T::AccountId) => T::TokenBalance;
+        ReservedBalanceOf get(reserved_balance_of): map (T::AssetId, T::AccountId) => T::TokenBalance;
+        Locks get(locks): map (T::AssetId, T::AccountId) => Vec<BalanceLock<T::TokenBalance, T::BlockNumber>>;
+        /// Keyed by asset, owner and spender.
+        Allowance get(allowance): map (T::AssetId, T::AccountId, T::AccountId) => T::TokenBalance;
+        LockedDeposits get(locked_deposits): map (T::AssetId, T::Hash) => T::TokenBalance;
+        HolderCount get(holder_count) build(|_: &GenesisConfig<T>| {
+            vec![(T::AssetId::default(), 1)]
+        }): map T::AssetId => u32;
+        /// Every account that has ever held an asset, indexed in the order they first did,
+        /// so the ledger can be audited.
+        Holders get(holder) build(|config: &GenesisConfig<T>| {
+            vec![((T::AssetId::default(), 0), config.owner.clone())]
+        }): map (T::AssetId, u32) => T::AccountId;
+        DepositHashCount get(deposit_hash_count): map T::AssetId => u32;
+        /// Every listing hash that has ever had a deposit locked in an asset.
+        DepositHashes get(deposit_hash): map (T::AssetId, u32) => T::Hash;
+        /// Number of checkpoints written for an account's holding of an asset.
+        CheckpointCount get(checkpoint_count) build(|config: &GenesisConfig<T>| {
+            vec![((T::AssetId::default(), config.owner.clone()), 1)]
+        }): map (T::AssetId, T::AccountId) => u32;
+        /// `(block, free + reserved balance)` pairs indexed oldest first, one per block the
+        /// balance changed in.
+        Checkpoints get(checkpoint) build(|config: &GenesisConfig<T>| {
+            vec![((T::AssetId::default(), config.owner.clone(), 0), (Zero::zero(), config.total_supply))]
+        }): map (T::AssetId, T::AccountId, u32) => (T::BlockNumber, T::TokenBalance);
+        Vesting get(vesting) build(|config: &GenesisConfig<T>| {
+            config.vesting.iter().map(|&(ref who, start, cliff, duration, locked)| {
+                let schedule = VestingSchedule { locked, start, cliff, duration };
+                assert!(schedule.is_valid(), "Vesting cliff cannot be longer than its duration.");
+                ((T::AssetId::default(), who.clone()), schedule)
+            }).collect::<Vec<_>>()
+        }): map (T::AssetId, T::AccountId) => Option<VestingSchedule<T::TokenBalance, T::VestingMoment>>;
+        /// Vesting schedules offered by one account to another, waiting to be accepted.
+        VestingOffers get(vesting_offer): map (T::AssetId, T::AccountId, T::AccountId)
+            => Option<VestingSchedule<T::TokenBalance, T::VestingMoment>>;
+        /// Accounts whose tokens of an asset cannot be moved or staked.
+        Frozen get(is_frozen): map (T::AssetId, T::AccountId) => bool;
+        /// Assets no account can move or stake.
+        Paused get(is_paused): map T::AssetId => bool;
+        /// Whether tokens still under a vesting schedule may be reserved as TCR stake.
+        StakeUnvested get(stake_unvested) config(): bool;
+        /// Flat fee charged per extrinsic when fees are paid in this token.
+        TransactionBaseFee get(transaction_base_fee) config(): T::TokenBalance;
+        /// Fee charged per byte of an encoded extrinsic when fees are paid in this token.
+        TransactionByteFee get(transaction_byte_fee) config(): T::TokenBalance;
+        /// Account transaction fees are paid to.
+        FeeAccount get(fee_account) config(): Option<T::AccountId>;
     }
+    add_extra_genesis {
+        config(name): Vec<u8>;
+        config(symbol): Vec<u8>;
+        config(decimals): u8;
+        config(total_supply): T::TokenBalance;
+        /// `(who, start, cliff, duration, locked)`, all in the genesis asset.
+        config(vesting): Vec<(T::AccountId, T::VestingMoment, T::VestingMoment, T::VestingMoment, T::TokenBalance)>;
+    }
+}
+
+/// A restriction on how much of an account's free balance may be withdrawn, and for what.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode,Decode,Clone,PartialEq)]
+pub struct BalanceLock<Balance, BlockNumber> {
+    pub id: LockIdentifier,
+    pub amount: Balance,
+    pub until: BlockNumber,
+    pub reasons: WithdrawReasons,
 }
 
 decl_event!(
-    pub enum Event<T> where AccountId = <T as system::Trait>::AccountId,TokenBalance = <T as self::Trait>::TokenBalance {
-        Transfer(AccountId,AccountId,TokenBalance),
-        Approval(AccountId,AccountId,TokenBalance),
+    pub enum Event<T> where AccountId = <T as system::Trait>::AccountId,
+    Hash = <T as system::Trait>::Hash,
+    TokenBalance = <T as self::Trait>::TokenBalance,
+    AssetId = <T as self::Trait>::AssetId {
+        /// New asset, its creator and its initial supply.
+        AssetCreated(AssetId,AccountId,TokenBalance),
+        Transfer(AssetId,AccountId,AccountId,TokenBalance),
+        /// Owner, spender and the allowance remaining after the change.
+        Approval(AssetId,AccountId,AccountId,TokenBalance),
+        Minted(AssetId,AccountId,TokenBalance),
+        Burned(AssetId,AccountId,TokenBalance),
+        /// Account, listing hash the deposit is locked under, and the amount locked.
+        Locked(AssetId,AccountId,Hash,TokenBalance),
+        /// Account, listing hash the deposit was locked under, and the amount returned.
+        Unlocked(AssetId,AccountId,Hash,TokenBalance),
+        /// Account and the amount moved from its free to its reserved balance.
+        Reserved(AssetId,AccountId,TokenBalance),
+        /// Account and the amount moved from its reserved back to its free balance.
+        Unreserved(AssetId,AccountId,TokenBalance),
+        /// Sender, recipient and the amount offered under a vesting schedule.
+        VestingOffered(AssetId,AccountId,AccountId,TokenBalance),
+        /// Account and the amount still locked by its vesting schedule.
+        Vested(AssetId,AccountId,TokenBalance),
+        /// New name, symbol and decimals.
+        MetadataSet(AssetId,Vec<u8>,Vec<u8>,u8),
+        AccountFrozen(AssetId,AccountId),
+        AccountThawed(AssetId,AccountId),
+        AssetPaused(AssetId),
+        AssetUnpaused(AssetId),
     }
 );
 
 impl<T: Trait> Module<T> {
-    pub fn init(sender: T::AccounId) -> Result {
-        ensure!(Self::is_init) == false, "Token already initialized.");
-
-        <Balance<of<T>>::insert(sender, Self::total_supply());
-        <Init<T>>::put(true);
-
-        Ok(())
-    }
-
-    pub fn lock(from: T::AccountId, value: T::TokenBalance, 
+    pub fn lock(asset_id: T::AssetId, from: T::AccountId, value: T::TokenBalance,
         listing_hash: T::Hash) -> Result {
-            ensure!(<BalanceOf<T>::exisits(from.clone()), 
+        ensure!(<BalanceOf<T>>::exists((asset_id, from.clone())),
             "Account does not own this token");
 
-        let sender_balance = Self::balance_of(from.clone());
-        ensure!(sender_balance > value, "Not enough balance.");
+        let sender_balance = Self::balance_of((asset_id, from.clone()));
+        ensure!(sender_balance >= value, "Not enough balance.");
         let updated_from_balance = sender_balance.checked_sub(&value)
-            .ok_or("overfloe in calculating balance")?;
-        let deposit = Self::locked_deposits(listing_hash);
+            .ok_or("overflow in calculating balance")?;
+        Self::ensure_can_withdraw(asset_id, &from, WithdrawReason::Reserve, updated_from_balance)?;
+        let deposit = Self::locked_deposits((asset_id, listing_hash));
         let updated_deposit = deposit.checked_add(&value)
             .ok_or("overflow in calculating deposit")?;
 
-        <BalanceOf<T>>::insert(from,updated_from_balance);
+        Self::set_free_balance(asset_id, &from, updated_from_balance);
 
-        <LockedDeposits<T>>::insert(listing_hash,updated_deposit);
+        if !<LockedDeposits<T>>::exists((asset_id, listing_hash)) {
+            let count = Self::deposit_hash_count(asset_id);
+            <DepositHashes<T>>::insert((asset_id, count), listing_hash);
+            <DepositHashCount<T>>::insert(asset_id, count + 1);
+        }
+        <LockedDeposits<T>>::insert((asset_id, listing_hash), updated_deposit);
 
+        Self::deposit_event(RawEvent::Locked(asset_id, from, listing_hash, value));
         Ok(())
-    }    
+    }
 
-    pub fn unlock(to: T::AccountId, value: T::TokenBalance,
+    pub fn unlock(asset_id: T::AssetId, to: T::AccountId, value: T::TokenBalance,
         listing_hash: T::Hash) -> Result {
-        
-        let to_balance = Self::balance_of(to.clone());
-        let updated_to_balance = to_balance.checked_Add(&value)
-            .ok_or("overfloe in calculating balance")?;
-        let deposit = Self::locked_deposits(listing_hash);
+
+        let to_balance = Self::balance_of((asset_id, to.clone()));
+        let updated_to_balance = to_balance.checked_add(&value)
+            .ok_or("overflow in calculating balance")?;
+        let deposit = Self::locked_deposits((asset_id, listing_hash));
         let updated_deposit = deposit.checked_sub(&value)
-            .ok_or("overfloe in calculating deposit")?;
+            .ok_or("overflow in calculating deposit")?;
+
+        Self::set_free_balance(asset_id, &to, updated_to_balance);
+
+        <LockedDeposits<T>>::insert((asset_id, listing_hash), updated_deposit);
+
+        Self::deposit_event(RawEvent::Unlocked(asset_id, to, listing_hash, value));
+        Ok(())
+    }
+
+    /// Reconcile an asset's total supply against the balances held in its ledger.
+    pub fn audit(asset_id: T::AssetId) -> AuditReport<T::TokenBalance> {
+        let mut report = AuditReport {
+            total_supply: Self::total_supply(asset_id),
+            ..Default::default()
+        };
+
+        for index in 0..Self::holder_count(asset_id) {
+            let key = (asset_id, Self::holder((asset_id, index)));
+            report.free = report.free.saturating_add(Self::balance_of(&key));
+            report.reserved = report.reserved.saturating_add(Self::reserved_balance_of(&key));
+        }
+        for index in 0..Self::deposit_hash_count(asset_id) {
+            let listing_hash = Self::deposit_hash((asset_id, index));
+            report.locked = report.locked.saturating_add(Self::locked_deposits((asset_id, listing_hash)));
+        }
+
+        report
+    }
+
+    /// Credit `value` new tokens to `to`, growing the total supply.
+    pub fn _mint(asset_id: T::AssetId, to: T::AccountId, value: T::TokenBalance) -> Result {
+        Self::ensure_can_mint(asset_id, &to, value)?;
+        let updated_total_supply = Self::total_supply(asset_id) + value;
+        let updated_to_balance = Self::balance_of((asset_id, to.clone())) + value;
+
+        <TotalSupply<T>>::insert(asset_id, updated_total_supply);
+        Self::set_free_balance(asset_id, &to, updated_to_balance);
+
+        Self::deposit_event(RawEvent::Minted(asset_id, to, value));
+        Ok(())
+    }
+
+    /// Destroy `value` tokens held by `from`, shrinking the total supply.
+    pub fn _burn(asset_id: T::AssetId, from: T::AccountId, value: T::TokenBalance) -> Result {
+        Self::ensure_can_burn(asset_id, &from, value)?;
+        let updated_from_balance = Self::balance_of((asset_id, from.clone())) - value;
+        let updated_total_supply = Self::total_supply(asset_id) - value;
+
+        Self::set_free_balance(asset_id, &from, updated_from_balance);
+        <TotalSupply<T>>::insert(asset_id, updated_total_supply);
+
+        Self::deposit_event(RawEvent::Burned(asset_id, from, value));
+        Ok(())
+    }
+
+    pub fn metadata(asset_id: T::AssetId) -> TokenMetadata {
+        TokenMetadata {
+            name: Self::name(asset_id),
+            symbol: Self::symbol(asset_id),
+            decimals: Self::decimals(asset_id),
+        }
+    }
 
-        <BalanceOf<T>>::insert(to, updated_to_balance);
+    /// Balance `who` held at the start of `block`, counting both free and reserved tokens.
+    pub fn balance_at(asset_id: T::AssetId, who: &T::AccountId, block: T::BlockNumber) -> T::TokenBalance {
+        // Find the first checkpoint written in or after `block`.
+        let (mut low, mut high) = (0, Self::checkpoint_count((asset_id, who.clone())));
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if Self::checkpoint((asset_id, who.clone(), mid)).0 < block {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        if low == 0 {
+            Zero::zero()
+        } else {
+            Self::checkpoint((asset_id, who.clone(), low - 1)).1
+        }
+    }
+
+    /// Check that `_mint` can credit `value` new tokens to `to`.
+    pub fn ensure_can_mint(asset_id: T::AssetId, to: &T::AccountId, value: T::TokenBalance) -> Result {
+        ensure!(<TotalSupply<T>>::exists(asset_id), "Asset does not exist.");
+        Self::total_supply(asset_id).checked_add(&value)
+            .ok_or("overflow in calculating total supply")?;
+        Self::balance_of((asset_id, to.clone())).checked_add(&value)
+            .ok_or("overflow in calculating balance")?;
+        Ok(())
+    }
+
+    /// Check that `_burn` can destroy `value` of `from`'s tokens.
+    pub fn ensure_can_burn(asset_id: T::AssetId, from: &T::AccountId, value: T::TokenBalance) -> Result {
+        Self::ensure_not_frozen(asset_id, from)?;
+        ensure!(<BalanceOf<T>>::exists((asset_id, from.clone())),
+            "Account does not own this token");
+        let from_balance = Self::balance_of((asset_id, from.clone()));
+        ensure!(from_balance >= value, "Not enough balance.");
+        Self::ensure_can_withdraw(asset_id, from, WithdrawReason::Transfer, from_balance - value)?;
+        Self::total_supply(asset_id).checked_sub(&value)
+            .ok_or("overflow in calculating total supply")?;
+        Ok(())
+    }
+
+    /// Check that neither `asset_id` nor `who`'s holding of it is frozen.
+    pub fn ensure_not_frozen(asset_id: T::AssetId, who: &T::AccountId) -> Result {
+        ensure!(!Self::is_paused(asset_id), "Asset is paused.");
+        ensure!(!Self::is_frozen((asset_id, who.clone())), "Account is frozen.");
+        Ok(())
+    }
+
+    /// Check that `who` may drop to `new_balance` free tokens of `asset_id` for `reason`.
+    pub fn ensure_can_withdraw(
+        asset_id: T::AssetId,
+        who: &T::AccountId,
+        reason: WithdrawReason,
+        new_balance: T::TokenBalance,
+    ) -> Result {
+        // Fees are still charged so that the freeze origin can always get a thaw or unpause through.
+        if reason != WithdrawReason::TransactionPayment {
+            Self::ensure_not_frozen(asset_id, who)?;
+        }
+
+        if reason != WithdrawReason::Reserve || !Self::stake_unvested() {
+            if let Some(schedule) = Self::vesting((asset_id, who.clone())) {
+                ensure!(new_balance >= schedule.locked_at(T::VestingClock::now()),
+                    "vesting balance too high to send value");
+            }
+        }
+
+        let locks = Self::locks((asset_id, who.clone()));
+        if locks.is_empty() {
+            return Ok(())
+        }
+
+        let now = <system::Module<T>>::block_number();
+        if locks.into_iter()
+            .all(|l| now >= l.until || new_balance >= l.amount || !l.reasons.contains(reason))
+        {
+            Ok(())
+        } else {
+            Err("account liquidity restrictions prevent withdrawal")
+        }
+    }
+
+    fn put_metadata(asset_id: T::AssetId, name: Vec<u8>, symbol: Vec<u8>, decimals: u8) -> Result {
+        ensure!(name.len() <= 64, "Token name cannot be more than 64 bytes");
+        ensure!(symbol.len() <= 16, "Token symbol cannot be more than 16 bytes");
 
-        <LockedDepsits<T>>::insert(listing_hash, updated_deposit);
+        <Name<T>>::insert(asset_id, name.clone());
+        <Symbol<T>>::insert(asset_id, symbol.clone());
+        <Decimals<T>>::insert(asset_id, decimals);
 
+        Self::deposit_event(RawEvent::MetadataSet(asset_id, name, symbol, decimals));
         Ok(())
     }
 
-    fn transfer(
+    fn set_free_balance(asset_id: T::AssetId, who: &T::AccountId, balance: T::TokenBalance) {
+        <BalanceOf<T>>::insert((asset_id, who.clone()), balance);
+        Self::write_checkpoint(asset_id, who);
+    }
+
+    fn set_reserved_balance(asset_id: T::AssetId, who: &T::AccountId, balance: T::TokenBalance) {
+        <ReservedBalanceOf<T>>::insert((asset_id, who.clone()), balance);
+        Self::write_checkpoint(asset_id, who);
+    }
+
+    fn write_checkpoint(asset_id: T::AssetId, who: &T::AccountId) {
+        let now = <system::Module<T>>::block_number();
+        let total = Self::balance_of((asset_id, who.clone()))
+            + Self::reserved_balance_of((asset_id, who.clone()));
+
+        let count = Self::checkpoint_count((asset_id, who.clone()));
+        // Every account's first balance change writes its first checkpoint.
+        if count == 0 {
+            let holder_count = Self::holder_count(asset_id);
+            <Holders<T>>::insert((asset_id, holder_count), who.clone());
+            <HolderCount<T>>::insert(asset_id, holder_count + 1);
+        }
+        // Later changes in the same block overwrite that block's checkpoint.
+        if count > 0 && Self::checkpoint((asset_id, who.clone(), count - 1)).0 == now {
+            <Checkpoints<T>>::insert((asset_id, who.clone(), count - 1), (now, total));
+        } else {
+            <Checkpoints<T>>::insert((asset_id, who.clone(), count), (now, total));
+            <CheckpointCount<T>>::insert((asset_id, who.clone()), count + 1);
+        }
+    }
+
+    /// Overwrite the amount `spender` may move out of `owner`'s balance.
+    fn set_allowance(asset_id: T::AssetId, owner: T::AccountId, spender: T::AccountId,
+        value: T::TokenBalance) {
+        <Allowance<T>>::insert((asset_id,owner.clone(),spender.clone()), value);
+        Self::deposit_event(RawEvent::Approval(asset_id, owner, spender, value));
+    }
+
+    fn _transfer(
+        asset_id: T::AssetId,
         from: T::AccountId,
         to: T::AccountId,
-        Value: T::TokenBalance,
+        value: T::TokenBalance,
     ) -> Result {
-        ensure!(<BalanceOf<T>>::exists(from.clone()),
+        ensure!(<BalanceOf<T>>::exists((asset_id, from.clone())),
             "Account does not own this token");
-        let sender_balance = Self::balance_of(from.clone());
+        let sender_balance = Self::balance_of((asset_id, from.clone()));
         ensure!(sender_balance >= value, "Not enough balance.");
         let updated_from_balance = sender_balance.checked_sub(&value)
-            .ok_or("overfloen in calculating balance")?;
-        let receiver_balance = Self::balance_of(to.clone());
+            .ok_or("overflow in calculating balance")?;
+        Self::ensure_can_withdraw(asset_id, &from, WithdrawReason::Transfer, updated_from_balance)?;
+        let receiver_balance = Self::balance_of((asset_id, to.clone()));
         let updated_to_balance = receiver_balance.checked_add(&value)
             .ok_or("overflow in calculating")?;
-        
-        <BalanceOf<T>>::insert(from.clone(),updated_from_balance);
-        <BalanceOf<T>>::insert(to.clone(),updated_to_balance);
 
-        Self::deposit_event(RawEvent::Transfer(from, to, value));
+        Self::set_free_balance(asset_id, &from, updated_from_balance);
+        Self::set_free_balance(asset_id, &to, updated_to_balance);
+
+        Self::deposit_event(RawEvent::Transfer(asset_id, from, to, value));
+        Ok(())
+    }
+}
+
+/// A single asset of the token module, seen through the SRML currency traits.
+pub struct AssetCurrency<T, A>(PhantomData<(T, A)>);
+
+impl<T: Trait, A: AssetIdProvider<T::AssetId>> Currency<T::AccountId> for AssetCurrency<T, A> {
+    type Balance = T::TokenBalance;
+    type PositiveImbalance = PositiveImbalance<T, A>;
+    type NegativeImbalance = NegativeImbalance<T, A>;
+
+    fn total_balance(who: &T::AccountId) -> Self::Balance {
+        let key = (A::asset_id(), who.clone());
+        <Module<T>>::balance_of(&key) + <Module<T>>::reserved_balance_of(&key)
+    }
+
+    fn can_slash(who: &T::AccountId, value: Self::Balance) -> bool {
+        Self::free_balance(who) >= value
+    }
+
+    fn total_issuance() -> Self::Balance {
+        <Module<T>>::total_supply(A::asset_id())
+    }
+
+    fn minimum_balance() -> Self::Balance {
+        Zero::zero()
+    }
+
+    fn free_balance(who: &T::AccountId) -> Self::Balance {
+        <Module<T>>::balance_of((A::asset_id(), who.clone()))
+    }
+
+    fn ensure_can_withdraw(
+        who: &T::AccountId,
+        _amount: Self::Balance,
+        reason: WithdrawReason,
+        new_balance: Self::Balance,
+    ) -> Result {
+        <Module<T>>::ensure_can_withdraw(A::asset_id(), who, reason, new_balance)
+    }
+
+    fn transfer(source: &T::AccountId, dest: &T::AccountId, value: Self::Balance) -> Result {
+        <Module<T>>::_transfer(A::asset_id(), source.clone(), dest.clone(), value)
+    }
+
+    fn withdraw(
+        who: &T::AccountId,
+        value: Self::Balance,
+        reason: WithdrawReason,
+        _liveness: ExistenceRequirement,
+    ) -> result::Result<Self::NegativeImbalance, &'static str> {
+        let asset_id = A::asset_id();
+        let new_balance = Self::free_balance(who).checked_sub(&value)
+            .ok_or("too few free funds in account")?;
+        <Module<T>>::ensure_can_withdraw(asset_id, who, reason, new_balance)?;
+
+        <Module<T>>::set_free_balance(asset_id, who, new_balance);
+        Ok(NegativeImbalance::new(value))
+    }
+
+    fn slash(who: &T::AccountId, value: Self::Balance) -> (Self::NegativeImbalance, Self::Balance) {
+        let free_balance = Self::free_balance(who);
+        let free_slash = cmp::min(free_balance, value);
+        <Module<T>>::set_free_balance(A::asset_id(), who, free_balance - free_slash);
+
+        let remaining_slash = value - free_slash;
+        if remaining_slash.is_zero() {
+            return (NegativeImbalance::new(free_slash), Zero::zero())
+        }
+
+        let (imbalance, remaining) = Self::slash_reserved(who, remaining_slash);
+        (imbalance.merge(NegativeImbalance::new(free_slash)), remaining)
+    }
+
+    fn deposit_into_existing(
+        who: &T::AccountId,
+        value: Self::Balance,
+    ) -> result::Result<Self::PositiveImbalance, &'static str> {
+        let asset_id = A::asset_id();
+        ensure!(<BalanceOf<T>>::exists((asset_id, who.clone())),
+            "beneficiary account must pre-exist");
+        let updated_balance = Self::free_balance(who).checked_add(&value)
+            .ok_or("overflow in calculating balance")?;
+
+        <Module<T>>::set_free_balance(asset_id, who, updated_balance);
+        Ok(PositiveImbalance::new(value))
+    }
+
+    fn deposit_creating(who: &T::AccountId, value: Self::Balance) -> Self::PositiveImbalance {
+        match Self::free_balance(who).checked_add(&value) {
+            Some(updated_balance) => {
+                <Module<T>>::set_free_balance(A::asset_id(), who, updated_balance);
+                PositiveImbalance::new(value)
+            },
+            None => PositiveImbalance::zero(),
+        }
+    }
+
+    fn make_free_balance_be(who: &T::AccountId, balance: Self::Balance) -> (
+        SignedImbalance<Self::Balance, Self::PositiveImbalance>,
+        UpdateBalanceOutcome,
+    ) {
+        let original = Self::free_balance(who);
+        let imbalance = if original <= balance {
+            SignedImbalance::Positive(PositiveImbalance::new(balance - original))
+        } else {
+            SignedImbalance::Negative(NegativeImbalance::new(original - balance))
+        };
+
+        <Module<T>>::set_free_balance(A::asset_id(), who, balance);
+        (imbalance, UpdateBalanceOutcome::Updated)
+    }
+}
+
+impl<T: Trait, A: AssetIdProvider<T::AssetId>> ReservableCurrency<T::AccountId> for AssetCurrency<T, A> {
+    fn can_reserve(who: &T::AccountId, value: Self::Balance) -> bool {
+        Self::free_balance(who).checked_sub(&value).map_or(false, |new_balance|
+            <Module<T>>::ensure_can_withdraw(A::asset_id(), who, WithdrawReason::Reserve, new_balance).is_ok()
+        )
+    }
+
+    fn reserved_balance(who: &T::AccountId) -> Self::Balance {
+        <Module<T>>::reserved_balance_of((A::asset_id(), who.clone()))
+    }
+
+    fn reserve(who: &T::AccountId, value: Self::Balance) -> Result {
+        let asset_id = A::asset_id();
+        let free_balance = Self::free_balance(who);
+        ensure!(free_balance >= value, "Not enough balance.");
+        let new_balance = free_balance - value;
+        <Module<T>>::ensure_can_withdraw(asset_id, who, WithdrawReason::Reserve, new_balance)?;
+        let updated_reserved = Self::reserved_balance(who).checked_add(&value)
+            .ok_or("overflow in calculating reserved balance")?;
+
+        <Module<T>>::set_free_balance(asset_id, who, new_balance);
+        <Module<T>>::set_reserved_balance(asset_id, who, updated_reserved);
+
+        <Module<T>>::deposit_event(RawEvent::Reserved(asset_id, who.clone(), value));
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn unreserve(who: &T::AccountId, value: Self::Balance) -> Self::Balance {
+        let asset_id = A::asset_id();
+        let reserved = Self::reserved_balance(who);
+        let actual = cmp::min(reserved, value);
+
+        <Module<T>>::set_reserved_balance(asset_id, who, reserved - actual);
+        <Module<T>>::set_free_balance(asset_id, who, Self::free_balance(who) + actual);
+
+        <Module<T>>::deposit_event(RawEvent::Unreserved(asset_id, who.clone(), actual));
+        value - actual
+    }
+
+    fn slash_reserved(who: &T::AccountId, value: Self::Balance) -> (Self::NegativeImbalance, Self::Balance) {
+        let reserved = Self::reserved_balance(who);
+        let slash = cmp::min(reserved, value);
+
+        <Module<T>>::set_reserved_balance(A::asset_id(), who, reserved - slash);
+        (NegativeImbalance::new(slash), value - slash)
+    }
+
+    fn repatriate_reserved(
+        slashed: &T::AccountId,
+        beneficiary: &T::AccountId,
+        value: Self::Balance,
+    ) -> result::Result<Self::Balance, &'static str> {
+        let asset_id = A::asset_id();
+        let reserved = Self::reserved_balance(slashed);
+        let slash = cmp::min(reserved, value);
+
+        <Module<T>>::set_reserved_balance(asset_id, slashed, reserved - slash);
+        <Module<T>>::set_free_balance(asset_id, beneficiary, Self::free_balance(beneficiary) + slash);
+        Ok(value - slash)
+    }
+}
+
+impl<T: Trait, A: AssetIdProvider<T::AssetId>> TransferReserved<T::AccountId, T::TokenBalance>
+    for AssetCurrency<T, A>
+{
+    fn transfer_reserved(from: &T::AccountId, to: &T::AccountId, value: T::TokenBalance) -> Result {
+        let asset_id = A::asset_id();
+        <Module<T>>::ensure_not_frozen(asset_id, from)?;
+        <Module<T>>::ensure_not_frozen(asset_id, to)?;
+
+        Self::slash_reserved_to(from, to, value)
+    }
+
+    fn slash_reserved_to(from: &T::AccountId, to: &T::AccountId, value: T::TokenBalance) -> Result {
+        let asset_id = A::asset_id();
+        let from_reserved = Self::reserved_balance(from).checked_sub(&value)
+            .ok_or("Not enough reserved balance.")?;
+        let to_reserved = Self::reserved_balance(to).checked_add(&value)
+            .ok_or("overflow in calculating reserved balance")?;
+
+        <Module<T>>::set_reserved_balance(asset_id, from, from_reserved);
+        <Module<T>>::set_reserved_balance(asset_id, to, to_reserved);
+        Ok(())
+    }
+}
+
+/// The native currency has no frozen accounts, so both moves repatriate the reserved funds and
+/// reserve them again on arrival.
+impl<T: balances::Trait> TransferReserved<T::AccountId, T::Balance> for balances::Module<T> {
+    fn transfer_reserved(from: &T::AccountId, to: &T::AccountId, value: T::Balance) -> Result {
+        Self::slash_reserved_to(from, to, value)
+    }
+
+    fn slash_reserved_to(from: &T::AccountId, to: &T::AccountId, value: T::Balance) -> Result {
+        ensure!(<Self as Currency<_>>::reserved_balance(from) >= value, "Not enough reserved balance.");
+        ensure!(!<Self as Currency<_>>::total_balance(to).is_zero(), "beneficiary account must pre-exist");
+        // Repatriating leaves the funds free, so check up front that they can be reserved again.
+        <Self as Currency<_>>::ensure_can_withdraw(to, value, WithdrawReason::Reserve,
+            <Self as Currency<_>>::free_balance(to))?;
+
+        <Self as ReservableCurrency<_>>::repatriate_reserved(from, to, value)?;
+        <Self as ReservableCurrency<_>>::reserve(to, value)
+    }
+}
+
+impl<T: Trait, A: AssetIdProvider<T::AssetId>> LockableCurrency<T::AccountId> for AssetCurrency<T, A> {
+    type Moment = T::BlockNumber;
+
+    fn set_lock(
+        id: LockIdentifier,
+        who: &T::AccountId,
+        amount: Self::Balance,
+        until: T::BlockNumber,
+        reasons: WithdrawReasons,
+    ) {
+        let key = (A::asset_id(), who.clone());
+        let now = <system::Module<T>>::block_number();
+        let mut new_lock = Some(BalanceLock { id, amount, until, reasons });
+        let mut locks = <Module<T>>::locks(&key).into_iter().filter_map(|lock|
+            if lock.id == id {
+                new_lock.take()
+            } else if lock.until > now {
+                Some(lock)
+            } else {
+                None
+            }).collect::<Vec<_>>();
+        if let Some(lock) = new_lock {
+            locks.push(lock)
+        }
+        <Locks<T>>::insert(key, locks);
+    }
+
+    fn extend_lock(
+        id: LockIdentifier,
+        who: &T::AccountId,
+        amount: Self::Balance,
+        until: T::BlockNumber,
+        reasons: WithdrawReasons,
+    ) {
+        let key = (A::asset_id(), who.clone());
+        let now = <system::Module<T>>::block_number();
+        let mut new_lock = Some(BalanceLock { id, amount, until, reasons });
+        let mut locks = <Module<T>>::locks(&key).into_iter().filter_map(|lock|
+            if lock.id == id {
+                new_lock.take().map(|nl| BalanceLock {
+                    id: lock.id,
+                    amount: cmp::max(lock.amount, nl.amount),
+                    until: cmp::max(lock.until, nl.until),
+                    reasons: lock.reasons | nl.reasons,
+                })
+            } else if lock.until > now {
+                Some(lock)
+            } else {
+                None
+            }).collect::<Vec<_>>();
+        if let Some(lock) = new_lock {
+            locks.push(lock)
+        }
+        <Locks<T>>::insert(key, locks);
+    }
+
+    fn remove_lock(id: LockIdentifier, who: &T::AccountId) {
+        let key = (A::asset_id(), who.clone());
+        let now = <system::Module<T>>::block_number();
+        let locks = <Module<T>>::locks(&key).into_iter()
+            .filter(|lock| lock.id != id && lock.until > now)
+            .collect::<Vec<_>>();
+        <Locks<T>>::insert(key, locks);
+    }
+}
+
+impl<T: Trait, A: AssetIdProvider<T::AssetId>> BalanceHistory<T::AccountId, T::BlockNumber, T::TokenBalance>
+    for AssetCurrency<T, A>
+{
+    fn balance_at(who: &T::AccountId, block: T::BlockNumber) -> T::TokenBalance {
+        <Module<T>>::balance_at(A::asset_id(), who, block)
+    }
+}
+
+/// Charges a flat fee plus a fee per byte. The executive only hands fee payment the
+/// extrinsic's encoded length, so its length is all that is charged for.
+impl<T: Trait, A: AssetIdProvider<T::AssetId>> MakePayment<T::AccountId> for AssetCurrency<T, A> {
+    fn make_payment(transactor: &T::AccountId, encoded_len: usize) -> Result {
+        let encoded_len = <T::TokenBalance as As<usize>>::sa(encoded_len);
+        let fee = <Module<T>>::transaction_base_fee()
+            .saturating_add(<Module<T>>::transaction_byte_fee().saturating_mul(encoded_len));
+        if fee.is_zero() {
+            return Ok(())
+        }
+
+        let imbalance = Self::withdraw(transactor, fee, WithdrawReason::TransactionPayment,
+            ExistenceRequirement::KeepAlive)?;
+        if let Some(fee_account) = <Module<T>>::fee_account() {
+            // Moving the fee rather than burning it leaves the total supply untouched.
+            let _ = imbalance.offset(Self::deposit_creating(&fee_account, fee));
+        }
+
+        Ok(())
+    }
+}
+
+/// Opaque wrappers around token amounts that keep `TotalSupply` in step when they are dropped.
+mod imbalances {
+    use super::{result,Imbalance,Trait,AssetIdProvider,PhantomData,TotalSupply};
+    use rstd::mem;
+    use runtime_primitives::traits::{Saturating,Zero};
+    use support::StorageMap;
+
+    /// Funds added to an account; dropping it raises the total supply.
+    #[must_use]
+    pub struct PositiveImbalance<T: Trait, A: AssetIdProvider<T::AssetId>>(T::TokenBalance, PhantomData<A>);
+
+    impl<T: Trait, A: AssetIdProvider<T::AssetId>> PositiveImbalance<T, A> {
+        pub fn new(amount: T::TokenBalance) -> Self {
+            PositiveImbalance(amount, PhantomData)
+        }
+    }
+
+    /// Funds removed from an account; dropping it lowers the total supply.
+    #[must_use]
+    pub struct NegativeImbalance<T: Trait, A: AssetIdProvider<T::AssetId>>(T::TokenBalance, PhantomData<A>);
+
+    impl<T: Trait, A: AssetIdProvider<T::AssetId>> NegativeImbalance<T, A> {
+        pub fn new(amount: T::TokenBalance) -> Self {
+            NegativeImbalance(amount, PhantomData)
+        }
+    }
+
+    impl<T: Trait, A: AssetIdProvider<T::AssetId>> Imbalance<T::TokenBalance> for PositiveImbalance<T, A> {
+        type Opposite = NegativeImbalance<T, A>;
+
+        fn zero() -> Self {
+            Self::new(Zero::zero())
+        }
+        fn drop_zero(self) -> result::Result<(), Self> {
+            if self.0.is_zero() {
+                Ok(())
+            } else {
+                Err(self)
+            }
+        }
+        fn split(self, amount: T::TokenBalance) -> (Self, Self) {
+            let first = self.0.min(amount);
+            let second = self.0 - first;
+
+            mem::forget(self);
+            (Self::new(first), Self::new(second))
+        }
+        fn merge(mut self, other: Self) -> Self {
+            self.0 = self.0.saturating_add(other.0);
+            mem::forget(other);
+
+            self
+        }
+        fn subsume(&mut self, other: Self) {
+            self.0 = self.0.saturating_add(other.0);
+            mem::forget(other);
+        }
+        fn offset(self, other: Self::Opposite) -> result::Result<Self, Self::Opposite> {
+            let (a, b) = (self.0, other.0);
+            mem::forget((self, other));
+
+            if a >= b {
+                Ok(Self::new(a - b))
+            } else {
+                Err(NegativeImbalance::new(b - a))
+            }
+        }
+        fn peek(&self) -> T::TokenBalance {
+            self.0
+        }
+    }
+
+    impl<T: Trait, A: AssetIdProvider<T::AssetId>> Imbalance<T::TokenBalance> for NegativeImbalance<T, A> {
+        type Opposite = PositiveImbalance<T, A>;
+
+        fn zero() -> Self {
+            Self::new(Zero::zero())
+        }
+        fn drop_zero(self) -> result::Result<(), Self> {
+            if self.0.is_zero() {
+                Ok(())
+            } else {
+                Err(self)
+            }
+        }
+        fn split(self, amount: T::TokenBalance) -> (Self, Self) {
+            let first = self.0.min(amount);
+            let second = self.0 - first;
+
+            mem::forget(self);
+            (Self::new(first), Self::new(second))
+        }
+        fn merge(mut self, other: Self) -> Self {
+            self.0 = self.0.saturating_add(other.0);
+            mem::forget(other);
+
+            self
+        }
+        fn subsume(&mut self, other: Self) {
+            self.0 = self.0.saturating_add(other.0);
+            mem::forget(other);
+        }
+        fn offset(self, other: Self::Opposite) -> result::Result<Self, Self::Opposite> {
+            let (a, b) = (self.0, other.0);
+            mem::forget((self, other));
+
+            if a >= b {
+                Ok(Self::new(a - b))
+            } else {
+                Err(PositiveImbalance::new(b - a))
+            }
+        }
+        fn peek(&self) -> T::TokenBalance {
+            self.0
+        }
+    }
+
+    impl<T: Trait, A: AssetIdProvider<T::AssetId>> Drop for PositiveImbalance<T, A> {
+        fn drop(&mut self) {
+            <TotalSupply<T>>::mutate(A::asset_id(), |v| *v = v.saturating_add(self.0));
+        }
+    }
+
+    impl<T: Trait, A: AssetIdProvider<T::AssetId>> Drop for NegativeImbalance<T, A> {
+        fn drop(&mut self) {
+            <TotalSupply<T>>::mutate(A::asset_id(), |v| *v = v.saturating_sub(self.0));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use primitives::{Blake2Hasher,H256};
+    use runtime_io::with_externalities;
+    use runtime_primitives::{
+        testing::{Digest,DigestItem,Header},
+        traits::{BlakeTwo256,IdentityLookup},
+        BuildStorage,
+    };
+    use support::{assert_noop,assert_ok,impl_outer_origin};
+
+    impl_outer_origin!{
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<u64>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+    impl Trait for Test {
+        type Event = ();
+        type TokenBalance = u64;
+        type AssetId = u32;
+        type ManagerOrigin = system::EnsureRoot<u64>;
+        type FreezeOrigin = system::EnsureRoot<u64>;
+        type VestingMoment = u64;
+        type VestingClock = system::Module<Test>;
+    }
+    impl balances::Trait for Test {
+        type Balance = u64;
+        type OnFreeBalanceZero = ();
+        type OnNewAccount = ();
+        type Event = ();
+        type TransactionPayment = ();
+        type DustRemoval = ();
+        type TransferPayment = ();
+    }
+    pub struct StakeAsset;
+    impl AssetIdProvider<u32> for StakeAsset {
+        fn asset_id() -> u32 {
+            0
+        }
+    }
+    type Token = Module<Test>;
+    type Stake = AssetCurrency<Test, StakeAsset>;
+    type Balances = balances::Module<Test>;
+
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let mut t = system::GenesisConfig::<Test>::default()
+            .build_storage()
+            .unwrap()
+            .0;
+        t.extend(
+            GenesisConfig::<Test> {
+                owner: 1,
+                name: b"Curation Token".to_vec(),
+                symbol: b"CRT".to_vec(),
+                decimals: 12,
+                total_supply: 1000,
+                vesting: vec![],
+                stake_unvested: false,
+                transaction_base_fee: 0,
+                transaction_byte_fee: 0,
+                fee_account: 0,
+            }
+            .build_storage()
+            .unwrap()
+            .0,
+        );
+        t.extend(
+            balances::GenesisConfig::<Test> {
+                balances: vec![(1, 100), (2, 20)],
+                ..Default::default()
+            }
+            .build_storage()
+            .unwrap()
+            .0,
+        );
+        t.into()
+    }
+
+    #[test]
+    fn should_mint_and_burn() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(
+                Token::mint(Origin::signed(1), 0, 2, 100),
+                "bad origin: expected to be a root origin"
+            );
+            assert_ok!(Token::mint(Origin::ROOT, 0, 2, 100));
+            assert_eq!(Token::balance_of((0, 2)), 100);
+            assert_eq!(Token::total_supply(0), 1100);
+
+            assert_ok!(Token::burn(Origin::signed(2), 0, 40));
+            assert_ok!(Token::burn_from(Origin::ROOT, 0, 1, 100));
+            assert_eq!(Token::balance_of((0, 2)), 60);
+            assert_eq!(Token::balance_of((0, 1)), 900);
+            assert_eq!(Token::total_supply(0), 960);
+        });
+    }
+
+    #[test]
+    fn should_fail_burn_more_than_balance() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Token::mint(Origin::ROOT, 0, 2, 10));
+            assert_noop!(Token::burn(Origin::signed(2), 0, 11), "Not enough balance.");
+        });
+    }
+
+    #[test]
+    fn should_spend_spender_allowance() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Token::approve(Origin::signed(1), 0, 2, 100));
+            assert_ok!(Token::approve(Origin::signed(1), 0, 2, 50));
+            assert_eq!(Token::allowance((0, 1, 2)), 50);
+
+            assert_noop!(
+                Token::transfer_from(Origin::signed(3), 0, 1, 3, 10),
+                "Allowance does not exists."
+            );
+            assert_noop!(
+                Token::transfer_from(Origin::signed(2), 0, 1, 3, 51),
+                "Not enough allowance"
+            );
+            assert_ok!(Token::transfer_from(Origin::signed(2), 0, 1, 3, 30));
+            assert_eq!(Token::allowance((0, 1, 2)), 20);
+            assert_eq!(Token::balance_of((0, 3)), 30);
+        });
+    }
+
+    #[test]
+    fn should_increase_and_decrease_allowance() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Token::increase_allowance(Origin::signed(1), 0, 2, 40));
+            assert_ok!(Token::decrease_allowance(Origin::signed(1), 0, 2, 15));
+            assert_eq!(Token::allowance((0, 1, 2)), 25);
+            assert_noop!(
+                Token::decrease_allowance(Origin::signed(1), 0, 2, 26),
+                "Allowance cannot be decreased below zero."
+            );
+        });
+    }
+
+    #[test]
+    fn should_release_vested_tokens_linearly() {
+        with_externalities(&mut new_test_ext(), || {
+            let overflowing = VestingSchedule { locked: 100, start: u64::max_value(), cliff: 5,
+                duration: 10 };
+            assert_noop!(Token::vested_transfer(Origin::signed(1), 0, 2, overflowing),
+                "Vesting cliff cannot be longer than its duration.");
+            let schedule = VestingSchedule { locked: 100, start: 0, cliff: 5, duration: 10 };
+            assert_ok!(Token::vested_transfer(Origin::signed(1), 0, 2, schedule));
+            // Nothing moves until the recipient accepts the schedule.
+            assert_eq!(Token::balance_of((0, 2)), 0);
+            assert_noop!(Token::accept_vesting(Origin::signed(3), 0, 1),
+                "No vesting schedule has been offered to you.");
+            assert_ok!(Token::accept_vesting(Origin::signed(2), 0, 1));
+            assert_eq!(Token::balance_of((0, 2)), 100);
+
+            system::Module::<Test>::set_block_number(4);
+            assert_noop!(Token::transfer(Origin::signed(2), 0, 3, 1),
+                "vesting balance too high to send value");
+            // Unvested tokens cannot be staked unless the runtime opts in.
+            assert!(!<Stake as ReservableCurrency<u64>>::can_reserve(&2, 1));
+
+            system::Module::<Test>::set_block_number(5);
+            assert_ok!(Token::transfer(Origin::signed(2), 0, 3, 50));
+            assert_noop!(Token::transfer(Origin::signed(2), 0, 3, 1),
+                "vesting balance too high to send value");
+
+            system::Module::<Test>::set_block_number(10);
+            assert_ok!(Token::vest(Origin::signed(2), 0));
+            assert_eq!(Token::vesting((0, 2)), None);
+            assert_ok!(Token::transfer(Origin::signed(2), 0, 3, 50));
+        });
+    }
+
+    #[test]
+    fn should_fail_burn_frozen_tokens() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Token::freeze_account(Origin::ROOT, 0, 1));
+            assert_noop!(Token::burn(Origin::signed(1), 0, 10), "Account is frozen.");
+            assert_ok!(Token::thaw_account(Origin::ROOT, 0, 1));
+
+            assert_ok!(Token::pause(Origin::ROOT, 0));
+            assert_noop!(Token::burn(Origin::signed(1), 0, 10), "Asset is paused.");
+            assert_ok!(Token::unpause(Origin::ROOT, 0));
+
+            assert_ok!(Token::burn(Origin::signed(1), 0, 10));
+            assert_eq!(Token::total_supply(0), 990);
+        });
+    }
+
+    #[test]
+    fn should_fail_burn_unvested_tokens() {
+        with_externalities(&mut new_test_ext(), || {
+            let schedule = VestingSchedule { locked: 100, start: 0, cliff: 5, duration: 10 };
+            assert_ok!(Token::vested_transfer(Origin::signed(1), 0, 2, schedule));
+            assert_ok!(Token::accept_vesting(Origin::signed(2), 0, 1));
+
+            assert_noop!(Token::burn(Origin::signed(2), 0, 1),
+                "vesting balance too high to send value");
+            assert_noop!(Token::burn_from(Origin::ROOT, 0, 2, 1),
+                "vesting balance too high to send value");
+
+            system::Module::<Test>::set_block_number(5);
+            assert_ok!(Token::burn(Origin::signed(2), 0, 50));
+            assert_noop!(Token::burn(Origin::signed(2), 0, 1),
+                "vesting balance too high to send value");
+            assert_eq!(Token::total_supply(0), 950);
+        });
+    }
+
+    #[test]
+    fn should_write_one_checkpoint_per_block() {
+        with_externalities(&mut new_test_ext(), || {
+            system::Module::<Test>::set_block_number(2);
+            assert_ok!(Token::transfer(Origin::signed(1), 0, 2, 100));
+            assert_ok!(Token::transfer(Origin::signed(1), 0, 2, 100));
+            system::Module::<Test>::set_block_number(4);
+            assert_ok!(Token::transfer(Origin::signed(2), 0, 3, 50));
+
+            assert_eq!(Token::checkpoint_count((0, 2)), 2);
+            assert_eq!(Token::balance_at(0, &2, 2), 0);
+            assert_eq!(Token::balance_at(0, &2, 3), 200);
+            assert_eq!(Token::balance_at(0, &2, 5), 150);
+            assert_eq!(Token::balance_at(0, &1, 1), 1000);
+        });
+    }
+
+    #[test]
+    fn should_charge_transaction_fees_in_token() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(<Stake as MakePayment<u64>>::make_payment(&1, 10));
+            assert_eq!(Token::balance_of((0, 1)), 1000);
+
+            assert_ok!(Token::set_fees(Origin::ROOT, 5, 1, Some(9)));
+            assert_ok!(<Stake as MakePayment<u64>>::make_payment(&1, 10));
+            assert_eq!(Token::balance_of((0, 1)), 985);
+            assert_eq!(Token::balance_of((0, 9)), 15);
+            assert_eq!(Token::total_supply(0), 1000);
+
+            assert_ok!(Token::set_fees(Origin::ROOT, 5, 0, None));
+            assert_ok!(<Stake as MakePayment<u64>>::make_payment(&1, 10));
+            assert_eq!(Token::total_supply(0), 995);
+
+            assert_ok!(Token::set_fees(Origin::ROOT, u64::max_value(), u64::max_value(), None));
+            assert!(<Stake as MakePayment<u64>>::make_payment(&1, 10).is_err());
+            assert!(<Stake as MakePayment<u64>>::make_payment(&2, 10).is_err());
+        });
+    }
+
+    #[test]
+    fn should_set_token_metadata() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_eq!(Token::metadata(0).decimals, 12);
+            assert_noop!(
+                Token::set_metadata(Origin::signed(1), 0, b"TCR".to_vec(), b"TCR".to_vec(), 18),
+                "bad origin: expected to be a root origin"
+            );
+            assert_ok!(Token::set_metadata(Origin::ROOT, 0, b"Registry Token".to_vec(), b"RGT".to_vec(), 18));
+            assert_eq!(Token::metadata(0), TokenMetadata {
+                name: b"Registry Token".to_vec(),
+                symbol: b"RGT".to_vec(),
+                decimals: 18,
+            });
+        });
+    }
+
+    #[test]
+    fn should_keep_asset_ledgers_separate() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Token::create_asset(Origin::signed(2), 500,
+                b"Second Token".to_vec(), b"SND".to_vec(), 6));
+            assert_eq!(Token::next_asset_id(), 2);
+            assert_eq!(Token::metadata(1).symbol, b"SND".to_vec());
+
+            assert_ok!(Token::transfer(Origin::signed(2), 1, 3, 200));
+            assert_eq!(Token::balance_of((1, 3)), 200);
+            assert_eq!(Token::balance_of((0, 3)), 0);
+            assert_eq!(Token::total_supply(0), 1000);
+            assert_eq!(Token::total_supply(1), 500);
+
+            // Only the staking asset can be reserved through `Stake`.
+            assert_noop!(<Stake as ReservableCurrency<u64>>::reserve(&2, 100), "Not enough balance.");
+            assert_noop!(Token::mint(Origin::ROOT, 7, 2, 100), "Asset does not exist.");
+        });
+    }
+
+    #[test]
+    fn should_block_frozen_accounts_and_paused_assets() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Token::transfer(Origin::signed(1), 0, 2, 300));
+            assert_ok!(Token::approve(Origin::signed(2), 0, 3, 100));
+            assert_noop!(Token::freeze_account(Origin::signed(1), 0, 2),
+                "bad origin: expected to be a root origin");
+            assert_ok!(Token::freeze_account(Origin::ROOT, 0, 2));
+
+            assert_noop!(Token::transfer(Origin::signed(2), 0, 3, 10), "Account is frozen.");
+            assert_noop!(Token::transfer_from(Origin::signed(3), 0, 2, 3, 10), "Account is frozen.");
+            assert_noop!(<Stake as ReservableCurrency<u64>>::reserve(&2, 100), "Account is frozen.");
+            assert_ok!(Token::thaw_account(Origin::ROOT, 0, 2));
+            assert_ok!(Token::transfer(Origin::signed(2), 0, 3, 10));
+
+            assert_ok!(Token::pause(Origin::ROOT, 0));
+            assert_noop!(Token::transfer(Origin::signed(1), 0, 3, 10), "Asset is paused.");
+            assert_ok!(Token::unpause(Origin::ROOT, 0));
+            assert_ok!(Token::transfer(Origin::signed(1), 0, 3, 10));
+        });
+    }
+
+    #[test]
+    fn should_reconcile_ledger_with_total_supply() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Token::transfer(Origin::signed(1), 0, 2, 300));
+            assert_ok!(<Stake as ReservableCurrency<u64>>::reserve(&2, 100));
+            assert_ok!(Token::lock(0, 1, 50, H256::repeat_byte(1)));
+            assert_ok!(Token::burn(Origin::signed(1), 0, 10));
+
+            let report = Token::audit(0);
+            assert_eq!(report, AuditReport { total_supply: 990, free: 840, reserved: 100, locked: 50 });
+            assert!(report.is_balanced());
+
+            assert_ok!(Token::unlock(0, 3, 50, H256::repeat_byte(1)));
+            assert_eq!(Token::audit(0).free, 890);
+            assert!(Token::audit(0).is_balanced());
+        });
+    }
+
+    #[test]
+    fn should_transfer_reserved_native_balance() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(<Balances as ReservableCurrency<u64>>::reserve(&1, 60));
+            assert_noop!(<Balances as TransferReserved<u64, u64>>::transfer_reserved(&1, &2, 61),
+                "Not enough reserved balance.");
+            assert_ok!(<Balances as TransferReserved<u64, u64>>::transfer_reserved(&1, &2, 50));
+            assert_eq!(<Balances as Currency<u64>>::reserved_balance(&1), 10);
+            assert_eq!(<Balances as Currency<u64>>::reserved_balance(&2), 50);
+            assert_eq!(<Balances as Currency<u64>>::free_balance(&2), 20);
+        });
+    }
+}